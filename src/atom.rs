@@ -0,0 +1,84 @@
+//! Support for Atom feeds.
+//!
+//! Rather than having a separate representation for Atom feeds, the `feed` and `entry`
+//! elements are mapped onto the keys of their RSS 2.0 counterparts. This way patterns,
+//! tags and the download tracker work the same regardless of the feed format.
+//! The original Atom keys are kept, so `{rss::episode::id}` and the like still work.
+
+use crate::utils;
+use crate::utils::{insert_missing, map_text};
+use serde_json::Map;
+use serde_json::Value;
+
+/// Adds the RSS `channel` keys to the map of an Atom `feed` element.
+pub fn map_feed(feed: &mut Map<String, Value>) {
+    if let Some(subtitle) = map_text(feed, "subtitle") {
+        insert_missing(feed, "description", subtitle);
+    }
+
+    if let Some(author) = author_name(feed) {
+        insert_missing(feed, "itunes:author", author);
+    }
+
+    if let Some(rights) = map_text(feed, "rights") {
+        insert_missing(feed, "copyright", rights);
+    }
+
+    if let Some(image) = map_text(feed, "logo").or_else(|| map_text(feed, "icon")) {
+        insert_missing(feed, "image", image);
+    }
+
+    if let Some(lang) = map_text(feed, "@xml:lang").or_else(|| map_text(feed, "@lang")) {
+        insert_missing(feed, "language", lang);
+    }
+}
+
+/// Adds the RSS `item` keys to the map of an Atom `entry` element.
+pub fn map_entry(entry: &mut Map<String, Value>) {
+    if let Some(id) = map_text(entry, "id") {
+        insert_missing(entry, "guid", id);
+    }
+
+    if let Some(date) = map_text(entry, "published").or_else(|| map_text(entry, "updated")) {
+        insert_missing(entry, "pubDate", date);
+    }
+
+    if let Some(summary) = map_text(entry, "summary").or_else(|| map_text(entry, "content")) {
+        insert_missing(entry, "description", summary);
+    }
+
+    if let Some(author) = author_name(entry) {
+        insert_missing(entry, "author", author);
+    }
+
+    if let Some(enclosure) = enclosure(entry) {
+        insert_missing(entry, "enclosure", enclosure);
+    }
+}
+
+/// Converts `<link rel="enclosure" href=".." type=".." length=".."/>` into the
+/// attributes of an RSS `<enclosure>` element.
+fn enclosure(entry: &Map<String, Value>) -> Option<Value> {
+    let link = utils::val_to_vec(entry.get("link")?)
+        .into_iter()
+        .find(|link| link.get("@rel").and_then(Value::as_str) == Some("enclosure"))?;
+
+    let mut enclosure = Map::new();
+    enclosure.insert("@url".to_string(), link.get("@href")?.clone());
+
+    if let Some(mime) = link.get("@type") {
+        enclosure.insert("@type".to_string(), mime.clone());
+    }
+
+    if let Some(length) = link.get("@length") {
+        enclosure.insert("@length".to_string(), length.clone());
+    }
+
+    Some(Value::Object(enclosure))
+}
+
+fn author_name(map: &Map<String, Value>) -> Option<String> {
+    let author = utils::val_to_vec(map.get("author")?).into_iter().next()?;
+    let name = author.get("name").unwrap_or(author);
+    utils::val_to_str(name).map(String::from)
+}
@@ -0,0 +1,137 @@
+//! Checking that a downloaded file is actually audio, and not an error page or garbage.
+//!
+//! Only the start of the file is inspected: an MPEG frame header (after an optional ID3v2 tag)
+//! or the header of one of the supported containers.
+
+use std::fs::File;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+
+/// How far into the file an MPEG frame sync is searched for.
+const SCAN_LEN: usize = 64 * 1024;
+
+pub fn verify(path: &Path) -> Result<(), String> {
+    let read_err = |_: std::io::Error| "failed to read downloaded file".to_string();
+    let mut file = File::open(path).map_err(read_err)?;
+
+    let mut header = vec![];
+    (&mut file)
+        .take(10)
+        .read_to_end(&mut header)
+        .map_err(read_err)?;
+
+    if header.is_empty() {
+        return Err("downloaded file is empty".to_string());
+    }
+
+    // The tag can be far larger than the scanned part when it has cover art embedded.
+    let mut data = vec![];
+    file.seek(SeekFrom::Start(id3v2_len(&header)))
+        .and_then(|_| file.take(SCAN_LEN as u64).read_to_end(&mut data))
+        .map_err(read_err)?;
+
+    let is_container = data.starts_with(b"fLaC")
+        || data.starts_with(b"OggS")
+        || data.get(4..8) == Some(b"ftyp")
+        || (data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WAVE"))
+        || (data.starts_with(b"FORM") && data.get(8..12) == Some(b"AIFF"));
+
+    if is_container || has_mpeg_frame(&data) {
+        Ok(())
+    } else {
+        Err("downloaded file isn't recognized as audio".to_string())
+    }
+}
+
+/// The length of a leading ID3v2 tag, which some FLAC and AAC files have as well.
+fn id3v2_len(header: &[u8]) -> u64 {
+    if header.len() < 10 || !header.starts_with(b"ID3") {
+        return 0;
+    }
+
+    // The size is a 28-bit synchsafe integer, excluding the header and optional footer.
+    let size = header[6..10]
+        .iter()
+        .fold(0u64, |acc, byte| (acc << 7) | (*byte & 0x7f) as u64);
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+
+    10 + size + footer
+}
+
+/// Whether the data contains two consecutive MPEG audio (or ADTS) frames near its start.
+fn has_mpeg_frame(data: &[u8]) -> bool {
+    let end = data.len().min(SCAN_LEN);
+
+    (0..end).any(|offset| {
+        let Some(len) = frame_len(&data[offset..]) else {
+            return false;
+        };
+
+        // A lone sync word is easily found in random data, so the next frame has to follow.
+        match data.get(offset + len..) {
+            Some([]) => true,
+            Some(next) => frame_len(next).is_some(),
+            None => false,
+        }
+    })
+}
+
+/// The length of the frame starting with the header, if it's a valid MPEG audio or ADTS header.
+fn frame_len(header: &[u8]) -> Option<usize> {
+    if header.len() < 6 || header[0] != 0xff || header[1] & 0xe0 != 0xe0 {
+        return None;
+    }
+
+    let version = (header[1] >> 3) & 0b11;
+    let layer = (header[1] >> 1) & 0b11;
+
+    // ADTS headers have a zero layer, which is reserved in MPEG audio.
+    if header[1] & 0xf6 == 0xf0 {
+        let len = ((header[3] as usize & 0b11) << 11)
+            | ((header[4] as usize) << 3)
+            | (header[5] as usize >> 5);
+        return (len > 7).then_some(len);
+    }
+
+    if version == 0b01 || layer == 0b00 {
+        return None;
+    }
+
+    let mpeg1 = version == 0b11;
+    let bitrates: [u32; 15] = match (mpeg1, layer) {
+        (true, 0b11) => [
+            0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+        ],
+        (true, 0b10) => [
+            0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+        ],
+        (true, _) => [
+            0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+        ],
+        (false, 0b11) => [
+            0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+        ],
+        (false, _) => [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+    };
+
+    let sample_rates: [u32; 3] = match version {
+        0b11 => [44100, 48000, 32000],
+        0b10 => [22050, 24000, 16000],
+        _ => [11025, 12000, 8000],
+    };
+
+    // Free format bitrates can't be validated this way.
+    let bitrate = *bitrates.get(header[2] as usize >> 4).filter(|x| **x != 0)? * 1000;
+    let sample_rate = *sample_rates.get((header[2] as usize >> 2) & 0b11)?;
+    let padding = (header[2] >> 1) & 1;
+
+    let len = match layer {
+        0b11 => (12 * bitrate / sample_rate + padding as u32) * 4,
+        0b01 if !mpeg1 => 72 * bitrate / sample_rate + padding as u32,
+        _ => 144 * bitrate / sample_rate + padding as u32,
+    };
+
+    Some(len as usize)
+}
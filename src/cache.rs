@@ -1,5 +1,6 @@
 use crate::display::DownloadBar;
 use crate::utils;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::{self, Read};
 use std::path::Path;
@@ -89,11 +90,8 @@ async fn write_image(url: &str, ui: &DownloadBar) -> Option<()> {
     Some(())
 }
 
-pub async fn get_image(
-    url: &str,
-    picture_type: id3::frame::PictureType,
-    ui: &DownloadBar,
-) -> Option<id3::frame::Frame> {
+/// The raw data of an image along with its mime type, fetching it if it isn't cached yet.
+pub async fn get_image_data(url: &str, ui: &DownloadBar) -> Option<(Vec<u8>, String)> {
     let data = match cached_image(url, ui) {
         Some(data) => data,
         None => {
@@ -110,6 +108,16 @@ pub async fn get_image(
         }
     };
 
+    Some((data, mime_type))
+}
+
+pub async fn get_image(
+    url: &str,
+    picture_type: id3::frame::PictureType,
+    ui: &DownloadBar,
+) -> Option<id3::frame::Frame> {
+    let (data, mime_type) = get_image_data(url, ui).await?;
+
     let pic = id3::frame::Picture {
         data,
         mime_type,
@@ -122,3 +130,81 @@ pub async fn get_image(
         id3::frame::Content::Picture(pic),
     ))
 }
+
+/// Response headers of the last successful fetch of a feed.
+///
+/// The validators are sent back to the server so that unchanged feeds can be answered
+/// with a `304 Not Modified`, in which case the content type tells us how to parse the cached copy.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct FeedHeaders {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_type: Option<String>,
+}
+
+impl FeedHeaders {
+    pub fn from_response(response: &reqwest::Response) -> Self {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+
+        Self {
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+            content_type: header(reqwest::header::CONTENT_TYPE),
+        }
+    }
+}
+
+/// Cached copies of podcast feeds, keyed by the hashed feed url.
+pub struct FeedCache;
+
+impl FeedCache {
+    fn dir() -> PathBuf {
+        let path = utils::cache_dir().join("feeds");
+        utils::create_dir(&path);
+        path
+    }
+
+    fn text_path(url: &str) -> PathBuf {
+        Self::dir().join(hashed_url(url))
+    }
+
+    fn headers_path(url: &str) -> PathBuf {
+        Self::dir().join(format!("{}.toml", hashed_url(url)))
+    }
+
+    /// Headers are only returned if the cached feed itself is still around,
+    /// otherwise a `304` response would leave us with nothing to parse.
+    pub fn headers(url: &str) -> Option<FeedHeaders> {
+        if !Self::text_path(url).exists() {
+            return None;
+        }
+
+        let s = fs::read_to_string(Self::headers_path(url)).ok()?;
+        toml::from_str(&s).ok()
+    }
+
+    pub fn text(url: &str) -> Option<String> {
+        fs::read_to_string(Self::text_path(url)).ok()
+    }
+
+    /// The cached feed along with the content type it was served with.
+    pub fn feed(url: &str) -> Option<utils::FeedText> {
+        Some(utils::FeedText {
+            text: Self::text(url)?,
+            content_type: Self::headers(url).and_then(|headers| headers.content_type),
+            moved_to: None,
+        })
+    }
+
+    pub fn save(url: &str, text: &str, headers: &FeedHeaders) -> Option<()> {
+        fs::write(Self::text_path(url), text).ok()?;
+        let headers = toml::to_string(headers).ok()?;
+        fs::write(Self::headers_path(url), headers).ok()
+    }
+}
@@ -0,0 +1,210 @@
+//! Chapters of an episode, and converting them into ID3v2 `CHAP` and `CTOC` frames.
+//!
+//! Chapters are read from a Podcasting 2.0 chapters file (<https://github.com/Podcastindex-org/podcast-namespace/blob/main/chapters/jsonChapters.md>)
+//! or from Podlove Simple Chapters (`psc:chapters`) embedded in the feed.
+
+use crate::cache;
+use crate::display::DownloadBar;
+use crate::episode::DownloadedEpisode;
+use crate::episode::XmlWrapper;
+use crate::utils;
+use serde::Deserialize;
+use std::time;
+
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    pub start: time::Duration,
+    pub end: Option<time::Duration>,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub img: Option<String>,
+    /// Whether the chapter is part of the table of contents.
+    pub toc: bool,
+}
+
+#[derive(Deserialize)]
+struct JsonChapters {
+    chapters: Vec<JsonChapter>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonChapter {
+    start_time: f64,
+    end_time: Option<f64>,
+    title: Option<String>,
+    img: Option<String>,
+    url: Option<String>,
+    toc: Option<bool>,
+}
+
+impl JsonChapter {
+    /// Chapters with a start time too large to be a duration are skipped.
+    fn into_chapter(self) -> Option<Chapter> {
+        let secs = |secs: f64| time::Duration::try_from_secs_f64(secs.max(0.)).ok();
+
+        Some(Chapter {
+            start: secs(self.start_time)?,
+            end: self.end_time.and_then(secs),
+            title: self.title,
+            url: self.url,
+            img: self.img,
+            toc: self.toc.unwrap_or(true),
+        })
+    }
+}
+
+fn parse_json(s: &str) -> Result<Vec<Chapter>, String> {
+    let chapters: JsonChapters =
+        serde_json::from_str(s).map_err(|e| format!("invalid chapters file: {}", e))?;
+
+    Ok(chapters
+        .chapters
+        .into_iter()
+        .filter_map(JsonChapter::into_chapter)
+        .collect())
+}
+
+/// Parses `<psc:chapters>` from the raw episode.
+fn parse_psc(episode: &DownloadedEpisode<'_>) -> Vec<Chapter> {
+    let Ok(psc) = episode.inner().attrs.raw.get_val("psc:chapters") else {
+        return vec![];
+    };
+
+    let Some(chapters) = psc.get("psc:chapter") else {
+        return vec![];
+    };
+
+    let attr = |chapter: &serde_json::Value, name: &str| {
+        chapter
+            .get(format!("@{}", name))
+            .and_then(utils::val_to_string)
+    };
+
+    utils::val_to_vec(chapters)
+        .into_iter()
+        .filter_map(|chapter| {
+            Some(Chapter {
+                start: utils::parse_duration(&attr(chapter, "start")?)?,
+                end: None,
+                title: attr(chapter, "title"),
+                url: attr(chapter, "href"),
+                img: attr(chapter, "image"),
+                toc: true,
+            })
+        })
+        .collect()
+}
+
+/// Loads the chapters of the episode.
+///
+/// A downloaded chapters sidecar file is preferred over fetching the chapters file again,
+/// and both are preferred over the simple chapters of the feed.
+pub async fn load(
+    episode: &DownloadedEpisode<'_>,
+    client: &reqwest::Client,
+    ui: &DownloadBar,
+) -> Vec<Chapter> {
+    let sidecar = episode
+        .sidecars()
+        .iter()
+        .find(|path| path.to_string_lossy().ends_with(".chapters.json"));
+
+    let json = match (sidecar, episode.inner().attrs.chapters()) {
+        (Some(path), _) => std::fs::read_to_string(path).ok(),
+        (None, Some(link)) => {
+            ui.log_debug("fetching chapters file");
+            match client.get(&link.url).send().await {
+                Ok(response) if response.status().is_success() => response.text().await.ok(),
+                _ => None,
+            }
+        }
+        (None, None) => None,
+    };
+
+    let mut chapters = match json.map(|json| parse_json(&json)) {
+        Some(Ok(chapters)) => chapters,
+        Some(Err(e)) => {
+            ui.log_warn(e);
+            parse_psc(episode)
+        }
+        None => parse_psc(episode),
+    };
+
+    chapters.sort_by_key(|chapter| chapter.start);
+    chapters
+}
+
+fn millis(duration: time::Duration) -> u32 {
+    u32::try_from(duration.as_millis()).unwrap_or(u32::MAX)
+}
+
+/// Converts the chapters into `CHAP` frames, along with a top-level `CTOC` frame listing them.
+///
+/// Chapters without an explicit end, end where the next one starts. The last one ends
+/// with the episode, or at its own start if the duration is unknown.
+pub async fn into_frames(
+    chapters: Vec<Chapter>,
+    duration: Option<time::Duration>,
+    ui: &DownloadBar,
+) -> Vec<id3::frame::Frame> {
+    use id3::frame::{Chapter as ChapterFrame, Content, ExtendedLink, Frame, TableOfContents};
+
+    let mut frames = vec![];
+    let mut toc_elements = vec![];
+
+    for (index, chapter) in chapters.iter().enumerate() {
+        let element_id = format!("chp{}", index);
+        let end = chapter
+            .end
+            .or_else(|| chapters.get(index + 1).map(|next| next.start))
+            .or(duration)
+            .unwrap_or(chapter.start);
+
+        let mut sub_frames = vec![];
+
+        if let Some(title) = &chapter.title {
+            sub_frames.push(Frame::text("TIT2", title));
+        }
+
+        if let Some(url) = &chapter.url {
+            let link = ExtendedLink {
+                description: "chapter url".to_string(),
+                link: url.clone(),
+            };
+            sub_frames.push(Frame::with_content("WXXX", Content::ExtendedLink(link)));
+        }
+
+        if let Some(img) = &chapter.img {
+            match cache::get_image(img, id3::frame::PictureType::Other, ui).await {
+                Some(frame) => sub_frames.push(frame),
+                None => ui.log_warn(format!("failed to fetch chapter image: {}", img)),
+            }
+        }
+
+        if chapter.toc {
+            toc_elements.push(element_id.clone());
+        }
+
+        frames.push(Frame::from(ChapterFrame {
+            element_id,
+            start_time: millis(chapter.start),
+            end_time: millis(end),
+            start_offset: u32::MAX,
+            end_offset: u32::MAX,
+            frames: sub_frames,
+        }));
+    }
+
+    if !toc_elements.is_empty() {
+        frames.push(Frame::from(TableOfContents {
+            element_id: "toc".to_string(),
+            top_level: true,
+            ordered: true,
+            elements: toc_elements,
+            frames: vec![],
+        }));
+    }
+
+    frames
+}
@@ -1,9 +1,17 @@
 use crate::display::DownloadBar;
+use crate::download_tracker::TrackerFormat;
 use crate::episode;
+use crate::episode_index::EpisodeIndex;
 use crate::patterns::Evaluate;
 use crate::patterns::FullPattern;
 use crate::podcast::Podcast;
 use crate::podcast::RawPodcast;
+use crate::redirects;
+use crate::retention::Retention;
+use crate::selector::EpisodeSelector;
+use crate::transfer::RateLimiter;
+use crate::transfer::RateWindow;
+use crate::transfer::TransferLimits;
 use crate::utils;
 use crate::utils::Unix;
 use futures::future;
@@ -13,13 +21,13 @@ use serde::de::IntoDeserializer;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::time;
 
 /// Represents a [`PodcastConfig`] value that is either enabled, disabled,
@@ -126,15 +134,21 @@ impl<'a> EvalData<'a> {
 /// Must be computed for every episode because config might contain patterns unique to episode.
 #[derive(Debug, Clone, Default)]
 pub struct Config {
+    pub podcast_name: String,
     pub url: String,
     pub name_pattern: String,
     pub id_pattern: String,
     pub download_path: PathBuf,
     pub partial_path: Option<PathBuf>,
     pub tracker_path: PathBuf,
+    pub tracker_format: TrackerFormat,
     pub symlink: Option<PathBuf>,
     pub id3_tags: HashMap<String, String>,
     pub download_hook: Option<PathBuf>,
+    pub transcript_formats: Vec<String>,
+    pub download_chapters: bool,
+    pub verify_audio: bool,
+    pub download_retry: RetrySettings,
 }
 
 impl Config {
@@ -167,7 +181,7 @@ impl Config {
             .download_path
             .unwrap_or_else(|| global_config.download_path.clone());
 
-        let download_path = FullPattern::direct_eval_dir(&download_path_str, data);
+        let download_path = FullPattern::direct_eval_path(&download_path_str, data);
 
         let tracker_path = match podcast_config
             .tracker_path
@@ -183,7 +197,11 @@ impl Config {
             }
         };
 
-        let tracker_path = FullPattern::direct_eval_file(&tracker_path, data);
+        let tracker_path = FullPattern::direct_eval_path(&tracker_path, data);
+
+        let tracker_format = podcast_config
+            .tracker_format
+            .unwrap_or(global_config.tracker_format);
 
         let name_pattern = FullPattern::from_str(
             &podcast_config
@@ -201,23 +219,42 @@ impl Config {
         let symlink = podcast_config
             .symlink
             .or(global_config.symlink.clone())
-            .map(|str| FullPattern::direct_eval_dir(str.as_ref(), data));
+            .map(|str| FullPattern::direct_eval_path(str.as_ref(), data));
 
         let partial_path = podcast_config
             .partial_path
             .or(global_config.partial_path.clone())
-            .map(|str| FullPattern::direct_eval_dir(str.as_ref(), data));
+            .map(|str| FullPattern::direct_eval_path(str.as_ref(), data));
+
+        let transcript_formats = podcast_config
+            .transcript_formats
+            .into_val(global_config.transcript_formats.as_ref())
+            .unwrap_or_default();
+
+        let download_chapters = podcast_config
+            .download_chapters
+            .unwrap_or(global_config.download_chapters);
+
+        let verify_audio = podcast_config
+            .verify_audio
+            .unwrap_or(global_config.verify_audio);
 
         Config {
+            podcast_name: data.pod_name.to_string(),
             url: podcast_config.url.clone(),
             name_pattern,
             id_pattern,
             download_path,
             partial_path,
             tracker_path,
+            tracker_format,
             symlink,
             id3_tags: id3_tags.clone(),
             download_hook: download_hook.clone(),
+            transcript_formats,
+            download_chapters,
+            verify_audio,
+            download_retry: global_config.download_retry.clone(),
         }
     }
 }
@@ -248,6 +285,65 @@ impl SearchSettings {
     }
 }
 
+/// A time of day with its own global rate limit, such as full speed at night.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RateScheduleEntry {
+    /// Local time in `HH:MM` format.
+    start: String,
+    end: String,
+    /// Bytes per second, unlimited if missing.
+    rate_limit: Option<u64>,
+}
+
+/// How failed feed fetches or enclosure downloads are retried.
+#[derive(Serialize, Default, Deserialize, Debug, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RetrySettings {
+    attempts: Option<u32>,
+    /// Delay before the first retry in milliseconds, doubling with every attempt.
+    backoff: Option<u64>,
+    /// Upper bound of the delay in milliseconds.
+    max_backoff: Option<u64>,
+    /// Randomizes the delays, so that failed transfers to the same host don't retry in lockstep.
+    jitter: Option<bool>,
+    status_codes: Option<Vec<u16>>,
+}
+
+impl RetrySettings {
+    fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Total number of attempts, including the first one.
+    pub fn attempts(&self) -> u32 {
+        self.attempts.unwrap_or(3).max(1)
+    }
+
+    pub fn jitter(&self) -> bool {
+        self.jitter.unwrap_or(true)
+    }
+
+    /// The delay before the given retry, starting at 1.
+    pub fn backoff(&self, retry: u32) -> time::Duration {
+        let backoff = self.backoff.unwrap_or(1000);
+        let factor = 2u64.saturating_pow(retry.saturating_sub(1));
+        time::Duration::from_millis(backoff.saturating_mul(factor)).min(self.max_backoff())
+    }
+
+    /// The longest wait before a retry, also for waits the server asks for.
+    pub fn max_backoff(&self) -> time::Duration {
+        time::Duration::from_millis(self.max_backoff.unwrap_or(60_000))
+    }
+
+    pub fn retries_status(&self, status: reqwest::StatusCode) -> bool {
+        match &self.status_codes {
+            Some(codes) => codes.contains(&status.as_u16()),
+            None => [408, 429, 500, 502, 503, 504].contains(&status.as_u16()),
+        }
+    }
+}
+
 #[derive(Serialize, Default, Deserialize, Debug, PartialEq, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct IndicatifSettings {
@@ -337,6 +433,24 @@ pub struct GlobalConfig {
     max_days: Option<i64>,
     max_episodes: Option<i64>,
     earliest_date: Option<String>,
+    keep_episodes: Option<usize>,
+    keep_days: Option<u64>,
+    max_size: Option<u64>,
+    transcript_formats: Option<Vec<String>>,
+    #[serde(default)]
+    download_chapters: bool,
+    #[serde(default)]
+    verify_audio: bool,
+    max_downloads: Option<usize>,
+    max_host_downloads: Option<usize>,
+    max_podcast_downloads: Option<usize>,
+    rate_limit: Option<u64>,
+    podcast_rate_limit: Option<u64>,
+    refresh_interval: Option<u64>,
+    #[serde(default)]
+    tracker_format: TrackerFormat,
+    #[serde(default)]
+    update_urls: bool,
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     id3_tags: HashMap<String, String>,
     download_hook: Option<PathBuf>,
@@ -349,6 +463,12 @@ pub struct GlobalConfig {
     symlink: Option<String>,
     #[serde(default, skip_serializing_if = "LogConfig::is_default")]
     log: Arc<LogConfig>,
+    #[serde(default, skip_serializing_if = "RetrySettings::is_default")]
+    feed_retry: RetrySettings,
+    #[serde(default, skip_serializing_if = "RetrySettings::is_default")]
+    download_retry: RetrySettings,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    rate_schedule: Vec<RateScheduleEntry>,
 }
 
 impl GlobalConfig {
@@ -421,12 +541,52 @@ impl GlobalConfig {
         Arc::clone(&self.log)
     }
 
+    /// Whether moved feeds get their url in `podcasts.toml` rewritten, see [`Podcast::follow_move`].
+    pub fn update_urls(&self) -> bool {
+        self.update_urls
+    }
+
+    pub fn feed_retry(&self) -> &RetrySettings {
+        &self.feed_retry
+    }
+
+    /// The limits shared by the transfers of all podcasts.
+    pub fn transfer_limits(&self) -> TransferLimits {
+        TransferLimits::new(
+            self.max_downloads,
+            self.max_host_downloads,
+            self.rate_limiter(),
+        )
+    }
+
+    /// The bandwidth limit shared by all podcasts, following the rate schedule.
+    fn rate_limiter(&self) -> RateLimiter {
+        let parse_time = |time: &str| match chrono::NaiveTime::parse_from_str(time, "%H:%M") {
+            Ok(time) => time,
+            Err(_) => {
+                eprintln!("invalid time in rate_schedule: {:?}, expected HH:MM", time);
+                process::exit(1);
+            }
+        };
+
+        let schedule = self
+            .rate_schedule
+            .iter()
+            .map(|entry| RateWindow {
+                start: parse_time(&entry.start),
+                end: parse_time(&entry.end),
+                rate: entry.rate_limit,
+            })
+            .collect();
+
+        RateLimiter::new(self.rate_limit, schedule)
+    }
+
     /// Serializes the config to the default path.
     pub fn save(&self) {
         let path = Self::default_path();
         let str = toml::to_string(self).unwrap();
-        let mut f = std::fs::File::create(&path).expect("unable to create config file");
-        f.write_all(str.as_bytes()).unwrap();
+        utils::write_atomic(&path, str).expect("unable to write config file");
     }
 
     pub fn user_agent(&self) -> String {
@@ -459,15 +619,32 @@ impl Default for GlobalConfig {
             max_days: None,
             max_episodes: Some(10),
             earliest_date: None,
+            keep_episodes: None,
+            keep_days: None,
+            max_size: None,
             id3_tags: Default::default(),
             download_hook: None,
             tracker_path: None,
+            tracker_format: TrackerFormat::default(),
+            update_urls: false,
             style: Default::default(),
             search: Default::default(),
             log: Default::default(),
             symlink: None,
             user_agent: None,
             partial_path: None,
+            transcript_formats: None,
+            download_chapters: false,
+            verify_audio: false,
+            max_downloads: None,
+            max_host_downloads: None,
+            max_podcast_downloads: None,
+            rate_limit: None,
+            podcast_rate_limit: None,
+            refresh_interval: None,
+            feed_retry: Default::default(),
+            download_retry: Default::default(),
+            rate_schedule: vec![],
         }
     }
 }
@@ -559,9 +736,10 @@ impl Default for DownloadMode {
     }
 }
 
-fn init_reqwest_client(config: &GlobalConfig) -> Arc<reqwest::Client> {
+pub fn init_reqwest_client(config: &GlobalConfig) -> Arc<reqwest::Client> {
     reqwest::Client::builder()
         .user_agent(&config.user_agent())
+        .redirect(redirects::policy())
         .build()
         .map(Arc::new)
         .expect("error: failed to instantiate reqwest client")
@@ -571,7 +749,19 @@ fn init_reqwest_client(config: &GlobalConfig) -> Arc<reqwest::Client> {
 pub struct PodcastConfigs(HashMap<String, PodcastConfig>);
 
 impl PodcastConfigs {
-    pub async fn sync(self, global_config: GlobalConfig, log_file: &Path) -> Vec<PathBuf> {
+    /// Syncs the podcasts concurrently, returning the paths of the downloaded episodes.
+    ///
+    /// On a dry run the planned downloads are printed and returned instead.
+    ///
+    /// Moved feeds get their url updated with `update_urls`, or if the config says so.
+    pub async fn sync(
+        self,
+        global_config: GlobalConfig,
+        log_file: &Path,
+        dry_run: bool,
+        update_urls: bool,
+    ) -> Vec<PathBuf> {
+        let update_urls = update_urls || global_config.update_urls();
         eprintln!("syncing {} podcasts", self.len());
         log::info!("syncing podcasts..");
 
@@ -584,6 +774,7 @@ impl PodcastConfigs {
         };
 
         let error_occured = Arc::new(AtomicBool::new(false));
+        let limits = Arc::new(global_config.transfer_limits());
 
         let futures = self
             .into_inner()
@@ -593,11 +784,19 @@ impl PodcastConfigs {
                 let settings = global_config.style();
                 let mut ui = DownloadBar::new(name.clone(), settings, &mp, longest_name);
                 let global_config = Arc::clone(&global_config);
+                let limits = Arc::clone(&limits);
                 let val = error_occured.clone();
 
                 tokio::task::spawn(async move {
-                    match Podcast::new(name, config, &global_config, client, &ui).await {
-                        Ok(podcast) => podcast.sync(&mut ui).await,
+                    match Podcast::new(name, config, &global_config, client, !dry_run, &ui).await {
+                        Ok(podcast) if dry_run => {
+                            podcast.follow_move(false, &ui);
+                            podcast.print_plan(&mut ui)
+                        }
+                        Ok(podcast) => {
+                            podcast.follow_move(update_urls, &ui);
+                            podcast.sync(&limits, &mut ui).await
+                        }
                         Err(e) => {
                             ui.error(&e);
                             val.store(true, Ordering::SeqCst);
@@ -627,6 +826,274 @@ impl PodcastConfigs {
         paths
     }
 
+    /// Downloads the selected episodes of the given podcast, see [`Podcast::download_selected`].
+    pub async fn download(
+        self,
+        name: &str,
+        selector: EpisodeSelector,
+        global_config: GlobalConfig,
+    ) -> Vec<PathBuf> {
+        let config = self.get_or_exit(name);
+
+        let mp = MultiProgress::new();
+        let client = init_reqwest_client(&global_config);
+        let longest_name = name.chars().count();
+        let mut ui = DownloadBar::new(name.to_string(), global_config.style(), &mp, longest_name);
+
+        match Podcast::new(name.to_string(), config, &global_config, client, true, &ui).await {
+            Ok(podcast) => {
+                let limits = global_config.transfer_limits();
+                podcast.download_selected(&selector, &limits, &mut ui).await
+            }
+            Err(e) => {
+                ui.error(&e);
+                vec![]
+            }
+        }
+    }
+
+    /// Prints the episodes of the given podcast, see [`Podcast::print_episodes`].
+    ///
+    /// Without the feed, the episodes known from the last sync are printed from the index.
+    pub async fn print_episodes(self, name: &str, global_config: GlobalConfig) {
+        let ui = DownloadBar::hidden(name.to_string(), global_config.style());
+        let config = self.get_or_exit(name);
+        let client = init_reqwest_client(&global_config);
+
+        match Podcast::new_or_cached(name.to_string(), config, &global_config, client, &ui).await {
+            Ok(podcast) => podcast.print_episodes(),
+            Err(e) => match EpisodeIndex::load(name) {
+                Ok(index) if !index.is_empty() => {
+                    eprintln!("warning: {}, printing the episodes of the last sync", e);
+                    index.print();
+                }
+                _ => {
+                    eprintln!("error: {}", e);
+                    process::exit(1);
+                }
+            },
+        }
+    }
+
+    /// Marks the selected episodes of the given podcast as downloaded, see [`Podcast::mark_selected`].
+    pub async fn mark(self, name: &str, selector: EpisodeSelector, global_config: GlobalConfig) {
+        let ui = DownloadBar::hidden(name.to_string(), global_config.style());
+        let podcast = self.podcast_or_exit(name, &global_config, &ui).await;
+
+        match podcast.mark_selected(&selector, &ui) {
+            Ok(marked) => eprintln!("{} episodes marked as downloaded.", marked),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    /// Unmarks the selected episodes of the given podcast, see [`Podcast::unmark_selected`].
+    pub async fn unmark(self, name: &str, selector: EpisodeSelector, global_config: GlobalConfig) {
+        let ui = DownloadBar::hidden(name.to_string(), global_config.style());
+        let podcast = self.podcast_or_exit(name, &global_config, &ui).await;
+
+        match podcast.unmark_selected(&selector, &ui) {
+            Ok(removed) => eprintln!("{} episodes unmarked.", removed),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    /// Prints the tracker entries of the given podcast, see [`Podcast::print_tracker`].
+    pub async fn print_tracker(self, name: &str, global_config: GlobalConfig) {
+        let ui = DownloadBar::hidden(name.to_string(), global_config.style());
+        let podcast = self.podcast_or_exit(name, &global_config, &ui).await;
+
+        if let Err(e) = podcast.print_tracker() {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+
+    /// Removes the given podcast, and with `delete_files` its files as well,
+    /// see [`Podcast::delete_files`].
+    pub async fn remove(mut self, name: &str, delete_files: bool, global_config: GlobalConfig) {
+        let config = self.get_or_exit(name);
+
+        if delete_files {
+            let ui = DownloadBar::hidden(name.to_string(), global_config.style());
+            let podcast = self.podcast_or_exit(name, &global_config, &ui).await;
+
+            if let Err(e) = podcast.delete_files(&config, &global_config) {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        }
+
+        self.0.remove(name);
+        self.save_to_file();
+
+        if let Err(e) = EpisodeIndex::remove(name) {
+            eprintln!("warning: {}", e);
+        }
+
+        eprintln!("'{}' removed!", name);
+    }
+
+    /// Renames the given podcast, and with `move_files` moves its files as well,
+    /// see [`Podcast::rename`].
+    pub async fn rename(
+        mut self,
+        name: &str,
+        new_name: &str,
+        move_files: bool,
+        global_config: GlobalConfig,
+    ) {
+        let config = self.get_or_exit(name);
+
+        if self.0.contains_key(new_name) {
+            eprintln!("'{}' already exists!", new_name);
+            process::exit(1);
+        }
+
+        let ui = DownloadBar::hidden(name.to_string(), global_config.style());
+        let client = init_reqwest_client(&global_config);
+        let podcast = Podcast::new_or_cached(
+            name.to_string(),
+            config.clone(),
+            &global_config,
+            client,
+            &ui,
+        )
+        .await;
+
+        // The feed is only needed for the files, the tracker entries can be renamed later on.
+        let result = match podcast {
+            Ok(podcast) => podcast.rename(new_name, &config, &global_config, move_files),
+            Err(e) if !move_files => {
+                eprintln!(
+                    "warning: {}, the tracker entries keep the old name and paths that follow from it aren't checked",
+                    e
+                );
+                Ok(())
+            }
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = result {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+
+        self.0.remove(name);
+        self.0.insert(new_name.to_string(), config);
+        self.save_to_file();
+
+        if let Err(e) = EpisodeIndex::rename(name, new_name) {
+            eprintln!("warning: {}", e);
+        }
+
+        eprintln!("'{}' renamed to '{}'!", name, new_name);
+    }
+
+    /// Loads the given podcast, from the cached feed if it can't be fetched.
+    async fn podcast_or_exit(
+        &self,
+        name: &str,
+        global_config: &GlobalConfig,
+        ui: &DownloadBar,
+    ) -> Podcast {
+        let config = self.get_or_exit(name);
+        let client = init_reqwest_client(global_config);
+
+        match Podcast::new_or_cached(name.to_string(), config, global_config, client, ui).await {
+            Ok(podcast) => podcast,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    /// Converts the download trackers of the podcasts to the JSON format.
+    ///
+    /// The feeds are fetched to fill in what the text format doesn't record.
+    pub async fn migrate_trackers(self, global_config: GlobalConfig) {
+        let succeeded = self
+            .migrate(&global_config, |podcast, _| {
+                let converted = podcast.migrate_trackers()?;
+                Ok(format!("converted {} entries", converted))
+            })
+            .await;
+
+        if global_config.tracker_format != TrackerFormat::Json {
+            eprintln!("set tracker_format = \"json\" in the config to keep recording new episodes as JSON");
+        }
+
+        if !succeeded {
+            process::exit(1);
+        }
+    }
+
+    /// Rewrites the tracker ids from an old `id_pattern`, see [`Podcast::migrate_ids`].
+    pub async fn migrate_ids(self, old_pattern: &str, global_config: GlobalConfig, dry_run: bool) {
+        let succeeded = self
+            .migrate(&global_config, |podcast, ui| {
+                let renamed = podcast.migrate_ids(old_pattern, dry_run, ui)?;
+                Ok(match dry_run {
+                    true => format!("{} entries would be changed", renamed),
+                    false => format!("changed {} entries", renamed),
+                })
+            })
+            .await;
+
+        if !succeeded {
+            process::exit(1);
+        }
+    }
+
+    /// Runs the migration on every podcast in turn, printing its outcome.
+    ///
+    /// Returns whether it succeeded for all of them.
+    async fn migrate(
+        self,
+        global_config: &GlobalConfig,
+        mut migrate: impl FnMut(&Podcast, &DownloadBar) -> Result<String, String>,
+    ) -> bool {
+        let client = init_reqwest_client(global_config);
+        let mut succeeded = true;
+
+        let mut podcasts: Vec<_> = self.into_inner().into_iter().collect();
+        podcasts.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (name, config) in podcasts {
+            let ui = DownloadBar::hidden(name.clone(), global_config.style());
+            let client = Arc::clone(&client);
+
+            let result = match Podcast::new_or_cached(
+                name.clone(),
+                config,
+                global_config,
+                client,
+                &ui,
+            )
+            .await
+            {
+                Ok(podcast) => migrate(&podcast, &ui),
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(outcome) => eprintln!("{}: {}", name, outcome),
+                Err(e) => {
+                    eprintln!("{}: error: {}", name, e);
+                    succeeded = false;
+                }
+            }
+        }
+
+        succeeded
+    }
+
     pub fn load() -> Self {
         let Ok(config_str) = fs::read_to_string(&Self::path()) else {
             eprintln!("error: failed to read podcasts.toml file");
@@ -642,7 +1109,17 @@ impl PodcastConfigs {
         }
     }
 
-    fn into_inner(self) -> HashMap<String, PodcastConfig> {
+    fn get_or_exit(&self, name: &str) -> PodcastConfig {
+        match self.0.get(name) {
+            Some(config) => config.clone(),
+            None => {
+                eprintln!("error: no podcast named '{}'", name);
+                process::exit(1);
+            }
+        }
+    }
+
+    pub fn into_inner(self) -> HashMap<String, PodcastConfig> {
         self.0
     }
 
@@ -710,12 +1187,10 @@ impl PodcastConfigs {
     }
 
     pub fn save_to_file(self) {
-        use std::fs::File;
-
         let str = toml::to_string(&self).expect("failed to serialize podcastconfigs");
         let path = Self::path();
 
-        if let Err(e) = File::create(&path).map(|mut file| file.write_all(str.as_bytes())) {
+        if let Err(e) = utils::write_atomic(&path, str) {
             eprintln!("failed to save podcast configs to file: {:?}", e);
             process::exit(1);
         };
@@ -732,6 +1207,22 @@ impl PodcastConfigs {
         podcasts.save_to_file();
     }
 
+    /// Changes the feed url of the podcast in the `podcasts.toml` file, returning the old url.
+    pub fn update_url(name: &str, url: &str) -> Result<String, String> {
+        // Podcasts are synced in parallel, their changes shouldn't overwrite each other.
+        static LOCK: Mutex<()> = Mutex::new(());
+        let _guard = LOCK.lock().unwrap();
+
+        let mut podcasts = Self::load();
+        let Some(config) = podcasts.0.get_mut(name) else {
+            return Err(format!("no podcast named '{}'", name));
+        };
+
+        let old_url = std::mem::replace(&mut config.url, url.to_string());
+        podcasts.save_to_file();
+        Ok(old_url)
+    }
+
     /// Appends the `podcast.toml` file with the given podcast.
     ///
     /// If a podcast with the same name already exist,
@@ -828,12 +1319,66 @@ pub struct PodcastConfig {
     max_days: ConfigOption<i64>,
     max_episodes: ConfigOption<i64>,
     earliest_date: ConfigOption<String>,
+    keep_episodes: ConfigOption<usize>,
+    keep_days: ConfigOption<u64>,
+    max_size: ConfigOption<u64>,
     download_hook: ConfigOption<PathBuf>,
     tracker_path: ConfigOption<String>,
+    tracker_format: Option<TrackerFormat>,
     symlink: Option<String>,
+    transcript_formats: ConfigOption<Vec<String>>,
+    download_chapters: Option<bool>,
+    verify_audio: Option<bool>,
+    max_podcast_downloads: Option<usize>,
+    podcast_rate_limit: Option<u64>,
+    refresh_interval: Option<u64>,
 }
 
 impl PodcastConfig {
+    /// How many episodes of the podcast may be downloaded at the same time.
+    pub fn max_downloads(&self, global_config: &GlobalConfig) -> usize {
+        self.max_podcast_downloads
+            .or(global_config.max_podcast_downloads)
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    /// How often the podcast is synced in daemon mode, configured in minutes.
+    pub fn refresh_interval(&self, global_config: &GlobalConfig) -> time::Duration {
+        let minutes = self
+            .refresh_interval
+            .or(global_config.refresh_interval)
+            .unwrap_or(60)
+            .max(1);
+        time::Duration::from_secs(minutes * 60)
+    }
+
+    /// Which downloaded episodes of the podcast are kept, see [`Retention`].
+    pub fn retention(&self, global_config: &GlobalConfig) -> Retention {
+        Retention {
+            keep_episodes: self
+                .keep_episodes
+                .into_val(global_config.keep_episodes.as_ref()),
+            keep_days: self.keep_days.into_val(global_config.keep_days.as_ref()),
+            max_size: self.max_size.into_val(global_config.max_size.as_ref()),
+        }
+    }
+
+    /// The unevaluated `name_pattern`, see [`Config::name_pattern`] for the evaluated one.
+    pub fn name_pattern(&self, global_config: &GlobalConfig) -> FullPattern {
+        let pattern = self
+            .name_pattern
+            .as_ref()
+            .unwrap_or(&global_config.name_pattern);
+        FullPattern::from_str(pattern)
+    }
+
+    /// The bandwidth limit shared by the downloads of the podcast.
+    pub fn rate_limiter(&self, global_config: &GlobalConfig) -> RateLimiter {
+        let rate = self.podcast_rate_limit.or(global_config.podcast_rate_limit);
+        RateLimiter::new(rate, vec![])
+    }
+
     pub fn new(url: String) -> Self {
         Self {
             url,
@@ -846,10 +1391,20 @@ impl PodcastConfig {
             max_days: Default::default(),
             max_episodes: Default::default(),
             earliest_date: Default::default(),
+            keep_episodes: Default::default(),
+            keep_days: Default::default(),
+            max_size: Default::default(),
             download_hook: Default::default(),
             tracker_path: Default::default(),
+            tracker_format: Default::default(),
             symlink: Default::default(),
             partial_path: Default::default(),
+            transcript_formats: Default::default(),
+            download_chapters: Default::default(),
+            verify_audio: Default::default(),
+            max_podcast_downloads: Default::default(),
+            podcast_rate_limit: Default::default(),
+            refresh_interval: Default::default(),
         }
     }
 
@@ -0,0 +1,155 @@
+//! Keeping the podcasts synced from a long-running process, instead of syncing once.
+//!
+//! Every podcast is synced on its own schedule: its `refresh_interval`, but never more
+//! often than the feed asks for with `<ttl>` or `sy:updatePeriod`. All podcasts share one
+//! http client and the global transfer limits.
+
+use crate::config::{self, GlobalConfig, PodcastConfig, PodcastConfigs};
+use crate::display::DownloadBar;
+use crate::lock::SharedLock;
+use crate::podcast::Podcast;
+use crate::transfer::TransferLimits;
+use futures::future;
+use std::sync::Arc;
+use std::time;
+use tokio::sync::watch;
+
+pub async fn run(podcasts: PodcastConfigs, global_config: GlobalConfig, update_urls: bool) {
+    eprintln!("watching {} podcasts", podcasts.len());
+    log::info!("starting daemon");
+
+    let update_urls = update_urls || global_config.update_urls();
+    let global_config = Arc::new(global_config);
+    let client = config::init_reqwest_client(&global_config);
+    let limits = Arc::new(global_config.transfer_limits());
+    let lock = Arc::new(SharedLock::default());
+    let (shutdown, shutdown_rx) = watch::channel(false);
+
+    let tasks: Vec<_> = podcasts
+        .into_inner()
+        .into_iter()
+        .map(|(name, config)| {
+            let watcher = Watcher {
+                name,
+                config,
+                global_config: Arc::clone(&global_config),
+                client: Arc::clone(&client),
+                limits: Arc::clone(&limits),
+                lock: Arc::clone(&lock),
+                update_urls,
+            };
+
+            tokio::task::spawn(watcher.run(shutdown_rx.clone()))
+        })
+        .collect();
+
+    wait_for_signal().await;
+    eprintln!("shutting down after the running syncs, send another signal to quit right away");
+    log::info!("shutting down daemon");
+    let _ = shutdown.send(true);
+
+    tokio::select! {
+        _ = future::join_all(tasks) => {}
+        _ = wait_for_signal() => log::warn!("shut down without waiting for running syncs"),
+    }
+}
+
+/// Waits for SIGINT, or SIGTERM on unix.
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut sigterm = signal(SignalKind::terminate()).expect("failed to listen for SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = sigterm.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+struct Watcher {
+    name: String,
+    config: PodcastConfig,
+    global_config: Arc<GlobalConfig>,
+    client: Arc<reqwest::Client>,
+    limits: Arc<TransferLimits>,
+    lock: Arc<SharedLock>,
+    update_urls: bool,
+}
+
+impl Watcher {
+    /// Syncs the podcast until a shutdown is requested.
+    ///
+    /// A sync that fails is tried again after the regular interval.
+    async fn run(mut self, mut shutdown: watch::Receiver<bool>) {
+        loop {
+            let hint = self.sync().await;
+
+            let interval = self.config.refresh_interval(&self.global_config);
+            let interval = hint.map_or(interval, |hint| interval.max(hint));
+            log::info!("{}: next sync in {}", &self.name, minutes(interval));
+
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = shutdown.changed() => return,
+            }
+        }
+    }
+
+    /// Syncs the podcast once, returning the refresh interval the feed asked for.
+    async fn sync(&mut self) -> Option<time::Duration> {
+        let mut ui = DownloadBar::hidden(self.name.clone(), self.global_config.style());
+
+        let _lock = match self.lock.hold().await {
+            Ok(lock) => lock,
+            Err(e) => {
+                log::error!("{}: {}", &self.name, e);
+                eprintln!("{}: {}", &self.name, e);
+                return None;
+            }
+        };
+        log::info!("{}: syncing", &self.name);
+
+        let podcast = Podcast::new(
+            self.name.clone(),
+            self.config.clone(),
+            &self.global_config,
+            Arc::clone(&self.client),
+            true,
+            &ui,
+        )
+        .await;
+
+        match podcast {
+            Ok(podcast) => {
+                // The next cycles go straight to the new url.
+                if let Some(url) = podcast.follow_move(self.update_urls, &ui) {
+                    self.config.url = url;
+                }
+
+                let hint = podcast.refresh_hint();
+                let paths = podcast.sync(&self.limits, &mut ui).await;
+                log::info!("{}: {} episodes downloaded", &self.name, paths.len());
+                if !paths.is_empty() {
+                    eprintln!("{}: {} episodes downloaded", &self.name, paths.len());
+                }
+                hint
+            }
+            Err(e) => {
+                log::error!("{}: {}", &self.name, e);
+                eprintln!("{}: {}", &self.name, e);
+                None
+            }
+        }
+    }
+}
+
+fn minutes(duration: time::Duration) -> String {
+    format!("{} min", duration.as_secs().div_ceil(60))
+}
@@ -4,6 +4,8 @@ use crate::utils;
 use indicatif::MultiProgress;
 use indicatif::ProgressBar;
 use indicatif::ProgressStyle;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 #[derive(Debug)]
@@ -13,6 +15,8 @@ pub struct DownloadBar {
     longest_podcast_name: usize,
     settings: Arc<IndicatifSettings>,
     completed: bool,
+    /// Number of transfers in flight, see [`DownloadBar::begin_transfer`].
+    transfers: AtomicUsize,
 }
 
 impl DownloadBar {
@@ -34,6 +38,19 @@ impl DownloadBar {
             podcast_name,
             longest_podcast_name,
             completed: false,
+            transfers: AtomicUsize::new(0),
+        }
+    }
+
+    /// A bar that only logs, for commands that print their own output.
+    pub fn hidden(podcast_name: String, settings: Arc<IndicatifSettings>) -> Self {
+        Self {
+            bar: None,
+            podcast_name,
+            longest_podcast_name: 0,
+            settings,
+            completed: false,
+            transfers: AtomicUsize::new(0),
         }
     }
 
@@ -108,6 +125,21 @@ impl DownloadBar {
         }
     }
 
+    /// Prints to stdout without getting mixed up with the progress bars.
+    pub fn println(&self, msg: &str) {
+        match &self.bar {
+            Some(pb) => pb.suspend(|| println!("{}", msg)),
+            None => println!("{}", msg),
+        }
+    }
+
+    pub fn eprintln(&self, msg: &str) {
+        match &self.bar {
+            Some(pb) => pb.suspend(|| eprintln!("{}", msg)),
+            None => eprintln!("{}", msg),
+        }
+    }
+
     pub fn set_template(&self, style: &str) {
         if let Some(pb) = &self.bar {
             pb.set_style(ProgressStyle::default_bar().template(style).unwrap());
@@ -126,6 +158,25 @@ impl DownloadBar {
         }
     }
 
+    /// Adds a transfer to the bar, which shows the combined progress of all transfers in flight.
+    ///
+    /// The bar starts over once a transfer begins with no others in flight.
+    pub fn begin_transfer(&self, start_point: u64, total_size: u64) -> Transfer<'_> {
+        if self.transfers.fetch_add(1, Ordering::SeqCst) == 0 {
+            self.init_download_bar(0, 0);
+        }
+
+        if let Some(pb) = &self.bar {
+            pb.inc_length(total_size);
+            pb.inc(start_point);
+        }
+
+        Transfer {
+            ui: self,
+            progress: start_point,
+        }
+    }
+
     pub fn set_progress(&self, progress: u64) {
         if let Some(pb) = &self.bar {
             pb.set_position(progress);
@@ -160,3 +211,24 @@ impl DownloadBar {
         }
     }
 }
+
+/// A transfer shown on a [`DownloadBar`], which is removed from it when dropped.
+pub struct Transfer<'a> {
+    ui: &'a DownloadBar,
+    progress: u64,
+}
+
+impl Transfer<'_> {
+    pub fn set_progress(&mut self, progress: u64) {
+        if let Some(pb) = &self.ui.bar {
+            pb.inc(progress.saturating_sub(self.progress));
+        }
+        self.progress = progress;
+    }
+}
+
+impl Drop for Transfer<'_> {
+    fn drop(&mut self) {
+        self.ui.transfers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
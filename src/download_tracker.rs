@@ -1,18 +1,202 @@
 use crate::episode::DownloadedEpisode;
+use crate::episode::Episode;
 use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs;
+use std::io::Read;
 use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// How new entries are written to the download tracker.
+///
+/// Both formats can be read, and they may be mixed within the same tracker.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackerFormat {
+    /// One `id unix "title"` line per episode, easy to read and to diff.
+    #[default]
+    Text,
+    /// One JSON object per line, see [`TrackerEntry`].
+    Json,
+}
+
+/// A downloaded episode, as recorded by the JSON tracker format.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrackerEntry {
+    pub id: String,
+    /// When the episode was downloaded, in unix seconds.
+    pub downloaded: u64,
+    pub title: String,
+    pub podcast: Option<String>,
+    pub url: Option<String>,
+    /// When the episode was published, in unix seconds.
+    pub published: Option<u64>,
+    pub path: Option<PathBuf>,
+    pub size: Option<u64>,
+    /// Hash of the file contents, prefixed with the algorithm, such as `fnv1a64:...`.
+    pub hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sidecars: Vec<String>,
+}
+
+impl TrackerEntry {
+    fn new(episode: &Episode) -> Self {
+        Self {
+            id: episode.get_id(),
+            downloaded: utils::current_unix().as_secs(),
+            title: episode.attrs.title().to_string(),
+            podcast: Some(episode.config.podcast_name.clone()),
+            url: Some(episode.attrs.url().to_string()),
+            published: Some(episode.attrs.published().as_secs()),
+            path: None,
+            size: None,
+            hash: None,
+            sidecars: vec![],
+        }
+    }
+
+    /// Records the size and hash of the file, if it's still there.
+    pub fn with_file(mut self, path: &Path) -> Self {
+        if let Ok(metadata) = fs::metadata(path) {
+            self.size = Some(metadata.len());
+            self.hash = hash_file(path);
+            self.path = Some(path.to_path_buf());
+        }
+
+        self
+    }
+
+    fn from_json(line: &str) -> Option<Self> {
+        match line.starts_with('{') {
+            true => serde_json::from_str(line).ok(),
+            false => None,
+        }
+    }
+
+    /// Parses a line of either tracker format.
+    ///
+    /// Ids may start with a brace as well, so a line is only JSON if it parses as such.
+    fn from_line(line: &str) -> Option<Self> {
+        if let Some(entry) = Self::from_json(line) {
+            return Some(entry);
+        }
+
+        let (id, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let (downloaded, rest) = rest
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .unwrap_or((rest.trim_start(), ""));
+        let mut quoted = quoted_fields(rest).into_iter();
+
+        Some(Self {
+            id: id.to_string(),
+            downloaded: downloaded.parse().unwrap_or_default(),
+            title: quoted.next().unwrap_or_default(),
+            podcast: None,
+            url: None,
+            published: None,
+            path: None,
+            size: None,
+            hash: None,
+            sidecars: quoted.collect(),
+        })
+    }
+
+    fn to_line(&self, format: TrackerFormat) -> String {
+        match format {
+            TrackerFormat::Json => serde_json::to_string(self).unwrap(),
+            TrackerFormat::Text => {
+                let mut line = format!("{} {} {}", self.id, self.downloaded, quote(&self.title));
+
+                // Sidecar files come after the title, the id is still the only field that's parsed.
+                for sidecar in &self.sidecars {
+                    line.push(' ');
+                    line.push_str(&quote(sidecar));
+                }
+
+                line
+            }
+        }
+    }
+}
+
+/// Quotes a field of the text format, escaping quotes and backslashes within it.
+fn quote(field: &str) -> String {
+    format!("\"{}\"", field.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Reads the quoted fields of a text line.
+///
+/// Older trackers didn't escape their fields, so an unescaped quote only ends a field
+/// when it's followed by whitespace or the end of the line.
+fn quoted_fields(s: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut chars = s.chars().peekable();
+
+    while chars.any(|c| c == '"') {
+        let mut field = String::new();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if matches!(chars.peek(), Some('"' | '\\')) => {
+                    field.extend(chars.next());
+                }
+                '"' if chars.peek().is_none_or(|c| c.is_whitespace()) => break,
+                c => field.push(c),
+            }
+        }
+
+        fields.push(field);
+    }
+
+    fields
+}
+
+fn same_but_id(a: &TrackerEntry, b: &TrackerEntry) -> bool {
+    let a = TrackerEntry {
+        id: b.id.clone(),
+        ..a.clone()
+    };
+
+    &a == b
+}
+
+fn hash_file(path: &Path) -> Option<String> {
+    use std::hash::Hasher;
+
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = fnv::FnvHasher::default();
+    let mut buffer = vec![0; 64 * 1024];
+
+    loop {
+        match file.read(&mut buffer).ok()? {
+            0 => break,
+            n => hasher.write(&buffer[..n]),
+        }
+    }
+
+    Some(format!("fnv1a64:{:016x}", hasher.finish()))
+}
 
 /// Keeps track of which episodes have already been downloaded.
 #[derive(Debug, Default)]
 pub struct DownloadedEpisodes(HashSet<String>);
 
+/// Podcasts are synced in parallel and may share a tracker.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
 impl DownloadedEpisodes {
     pub fn contains_episode(&self, episode_id: &str) -> bool {
         self.0.contains(episode_id)
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn load(path: &Path) -> Self {
         let s = match fs::read_to_string(path) {
             Ok(s) => s,
@@ -25,18 +209,164 @@ impl DownloadedEpisodes {
         let mut hashmap: HashSet<String> = HashSet::new();
 
         for line in s.trim().lines() {
-            let mut parts = line.split_whitespace();
-            if let Some(id) = parts.next() {
-                hashmap.insert(id.to_string());
+            if let Some(entry) = TrackerEntry::from_line(line.trim()) {
+                hashmap.insert(entry.id);
             }
         }
 
         Self(hashmap)
     }
 
-    pub fn append(path: &Path, id: &str, episode: &DownloadedEpisode) -> Result<(), String> {
-        use std::io::Write;
+    /// Adds the episode to the tracker.
+    ///
+    /// The tracker is rewritten as a whole rather than appended to, so that it's never left
+    /// with half a line when the process is interrupted.
+    pub fn append(path: &Path, episode: &DownloadedEpisode) -> Result<(), String> {
+        let format = episode.inner().config.tracker_format;
+
+        let mut entry = TrackerEntry {
+            sidecars: episode
+                .sidecars()
+                .iter()
+                .filter_map(|sidecar| sidecar.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .collect(),
+            ..TrackerEntry::new(episode.inner())
+        };
+
+        // Hashing reads the whole file, which is wasted on the text format.
+        if format == TrackerFormat::Json {
+            entry = entry.with_file(episode.path());
+        }
+
+        Self::write_lines(path, |lines| lines.push(entry.to_line(format)))
+    }
+
+    /// Adds the episode to the tracker without downloading it.
+    pub fn mark(path: &Path, episode: &Episode) -> Result<(), String> {
+        let line = TrackerEntry::new(episode).to_line(episode.config.tracker_format);
+        Self::write_lines(path, |lines| lines.push(line))
+    }
+
+    /// Removes the episodes with the given ids from the tracker, returning how many
+    /// entries were removed.
+    pub fn unmark(path: &Path, ids: &HashSet<String>) -> Result<usize, String> {
+        Self::update(path, |entry| match ids.contains(&entry.id) {
+            true => None,
+            false => Some(entry),
+        })
+    }
+
+    /// Replaces the ids of entries, returning how many entries were changed.
+    ///
+    /// Every entry keeps the format it was written in.
+    pub fn rename_ids(path: &Path, renames: &HashMap<String, String>) -> Result<usize, String> {
+        Self::update(path, |entry| match renames.get(&entry.id) {
+            Some(id) => Some(TrackerEntry {
+                id: id.clone(),
+                ..entry
+            }),
+            None => Some(entry),
+        })
+    }
+
+    /// Changes or removes entries, returning how many entries were changed or removed.
+    ///
+    /// Entries are removed when `update` returns `None`. Every entry keeps the format
+    /// it was written in.
+    pub fn update(
+        path: &Path,
+        mut update: impl FnMut(TrackerEntry) -> Option<TrackerEntry>,
+    ) -> Result<usize, String> {
+        let mut updated = 0;
+
+        Self::write_lines(path, |lines| {
+            lines.retain_mut(|line| {
+                let format = match TrackerEntry::from_json(line) {
+                    Some(_) => TrackerFormat::Json,
+                    None => TrackerFormat::Text,
+                };
+
+                let Some(entry) = TrackerEntry::from_line(line) else {
+                    return true;
+                };
+
+                match update(entry.clone()) {
+                    Some(new) if new == entry => true,
+                    // Text lines only get their id replaced, the rest is kept as it was written.
+                    Some(new) if format == TrackerFormat::Text && same_but_id(&new, &entry) => {
+                        *line = format!("{}{}", new.id, &line[entry.id.len()..]);
+                        updated += 1;
+                        true
+                    }
+                    Some(new) => {
+                        *line = new.to_line(format);
+                        updated += 1;
+                        true
+                    }
+                    None => {
+                        updated += 1;
+                        false
+                    }
+                }
+            })
+        })?;
 
+        Ok(updated)
+    }
+
+    /// All the entries of the tracker, in the order they were added.
+    pub fn entries(path: &Path) -> Result<Vec<TrackerEntry>, String> {
+        let s = match fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(_) => return Err("failed to read tracker file".to_string()),
+        };
+
+        Ok(s.lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| TrackerEntry::from_line(line.trim()))
+            .collect())
+    }
+
+    /// Converts the tracker to the JSON format, returning how many entries were converted.
+    ///
+    /// Entries are passed to `enrich` to fill in what the text format doesn't record, and
+    /// only the ones it changes are rewritten. JSON entries are left as they are once they
+    /// know their podcast, until then another podcast sharing the tracker may still claim them.
+    pub fn migrate(
+        path: &Path,
+        mut enrich: impl FnMut(TrackerEntry) -> TrackerEntry,
+    ) -> Result<usize, String> {
+        let mut converted = 0;
+
+        Self::write_lines(path, |lines| {
+            for line in lines.iter_mut() {
+                let entry = match TrackerEntry::from_json(line) {
+                    Some(entry) if entry.podcast.is_some() => continue,
+                    Some(entry) => entry,
+                    None => match TrackerEntry::from_line(line) {
+                        Some(entry) => entry,
+                        None => continue,
+                    },
+                };
+
+                // Entries that weren't enriched are left as they were written.
+                let enriched = enrich(entry.clone());
+                if enriched == entry {
+                    continue;
+                }
+
+                *line = enriched.to_line(TrackerFormat::Json);
+                converted += 1;
+            }
+        })?;
+
+        Ok(converted)
+    }
+
+    /// Rewrites the tracker atomically after modifying its lines.
+    fn write_lines(path: &Path, modify: impl FnOnce(&mut Vec<String>)) -> Result<(), String> {
         if path.is_dir() {
             eprintln!("error: invalid download tracker path: {:?}", path);
             eprintln!("download tracker cannot point to a directory");
@@ -47,21 +377,25 @@ impl DownloadedEpisodes {
             utils::create_dir(&parent)
         }
 
-        let mut file = fs::OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(path)
-            .map_err(|_| "failed to open tracker file".to_string())?;
-
-        writeln!(
-            file,
-            "{} {} \"{}\"",
-            id,
-            utils::current_unix().as_secs(),
-            episode.inner().attrs.title()
-        )
-        .unwrap();
-
-        Ok(())
+        let _guard = WRITE_LOCK.lock().unwrap();
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(_) => return Err("failed to read tracker file".to_string()),
+        };
+
+        let mut lines: Vec<String> = contents
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        modify(&mut lines);
+
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+
+        utils::write_atomic(path, contents).map_err(|_| "failed to write tracker file".to_string())
     }
 }
@@ -1,11 +1,21 @@
+use crate::audio;
 use crate::cache;
+use crate::chapters;
 use crate::config::Config;
 use crate::config::DownloadMode;
 use crate::display::DownloadBar;
 use crate::download_tracker::DownloadedEpisodes;
+use crate::partial::ContentRange;
+use crate::partial::ResumeMeta;
+use crate::podcast_ns;
+use crate::retry;
+use crate::retry::Failure;
+use crate::tags;
+use crate::transfer::RateLimiter;
+use crate::transfer::TransferLimits;
 use crate::utils;
 use futures_util::StreamExt;
-use std::cmp;
+use std::ffi::OsStr;
 use std::fs;
 use std::io::Seek;
 use std::io::Write as IOWrite;
@@ -100,10 +110,19 @@ impl Attributes {
         self.published
     }
 
-    pub fn _mime(&self) -> Option<&str> {
+    pub fn mime(&self) -> Option<&str> {
         self.mime.as_deref()
     }
 
+    /// Size of the enclosure in bytes, as stated by the feed.
+    ///
+    /// A length of zero is commonly used when the size is unknown, so it's treated as missing.
+    pub fn length(&self) -> Option<u64> {
+        let enclosure = self.raw.get_val("enclosure").ok()?;
+        let length = utils::val_to_string(enclosure.get("@length")?)?;
+        length.trim().parse().ok().filter(|length| *length > 0)
+    }
+
     pub fn title(&self) -> &str {
         &self.title
     }
@@ -120,6 +139,10 @@ impl Attributes {
         self.raw.get_str(key)
     }
 
+    pub fn get_text(&self, key: &str) -> Option<String> {
+        utils::val_to_string(self.raw.get_val(key).ok()?)
+    }
+
     pub fn image(&self) -> Result<&str, String> {
         let key = "itunes:image";
         self.raw.get_url(key)
@@ -132,21 +155,83 @@ impl Attributes {
         self.get_str("description")
     }
 
-    pub fn itunes_episode(&self) -> Result<&str, String> {
-        let key = "itunes:episode";
-        self.get_str(&key)
-    }
-
     pub fn itunes_duration(&self) -> Result<&str, String> {
         let key = "itunes:duration";
         self.get_str(&key)
     }
+
+    pub fn transcripts(&self) -> Vec<podcast_ns::Transcript> {
+        podcast_ns::transcripts(self.raw.inner())
+    }
+
+    pub fn chapters(&self) -> Option<podcast_ns::ChaptersLink> {
+        podcast_ns::chapters(self.raw.inner())
+    }
+
+    /// The `podcast:season`, falling back to `itunes:season`.
+    pub fn season(&self) -> Option<podcast_ns::Season> {
+        podcast_ns::season(self.raw.inner()).or_else(|| {
+            let number = self.get_text("itunes:season")?.parse().ok()?;
+            Some(podcast_ns::Season { number, name: None })
+        })
+    }
+
+    /// The `podcast:episode`, falling back to `itunes:episode`.
+    pub fn episode_number(&self) -> Option<podcast_ns::EpisodeNumber> {
+        podcast_ns::episode(self.raw.inner()).or_else(|| {
+            let number = self.get_text("itunes:episode")?;
+            Some(podcast_ns::EpisodeNumber {
+                number,
+                display: None,
+            })
+        })
+    }
+
+    pub fn persons(&self) -> Vec<podcast_ns::Person> {
+        podcast_ns::persons(self.raw.inner())
+    }
+
+    pub fn value(&self) -> Option<podcast_ns::ValueBlock> {
+        podcast_ns::value(self.raw.inner())
+    }
+
+    pub fn location(&self) -> Option<podcast_ns::Location> {
+        podcast_ns::location(self.raw.inner())
+    }
+}
+
+/// The outcome of filtering an episode by the download mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Accepted,
+    /// Published longer than `max_days` ago.
+    MaxDays,
+    /// Not among the latest `max_episodes` episodes.
+    MaxEpisodes,
+    /// Published before `earliest_date`.
+    EarliestDate,
+    /// Not yet reached in backlog mode.
+    Backlog,
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Accepted => "accepted",
+            Self::MaxDays => "rejected: max_days",
+            Self::MaxEpisodes => "rejected: max_episodes",
+            Self::EarliestDate => "rejected: earliest_date",
+            Self::Backlog => "rejected: backlog",
+        };
+
+        write!(f, "{}", s)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Episode {
     pub config: Config,
-    pub tags: Option<id3::Tag>,
+    pub tags: tags::Metadata,
     pub index: usize,
     pub attrs: Attributes,
     pub image_url: Option<String>,
@@ -157,7 +242,7 @@ impl Episode {
         attrs: Attributes,
         index: usize,
         config: Config,
-        tags: Option<id3::Tag>,
+        tags: tags::Metadata,
         image_url: Option<String>,
     ) -> Self {
         Self {
@@ -195,18 +280,19 @@ impl Episode {
         ui.log_debug(msg);
     }
 
-    fn is_downloaded(&self) -> bool {
-        let id = self.get_id();
-        let path = self.tracker_path();
-        DownloadedEpisodes::load(&path).contains_episode(&id)
+    pub fn is_downloaded(&self, tracker: &DownloadedEpisodes) -> bool {
+        tracker.contains_episode(&self.get_id())
     }
 
-    pub fn should_download(&self, mode: &DownloadMode, episode_qty: usize) -> bool {
-        let passed_filter = match mode {
+    /// Whether the episode passes the filter of the download mode.
+    pub fn verdict(&self, mode: &DownloadMode, episode_qty: usize) -> Verdict {
+        match mode {
             DownloadMode::Backlog { start, interval } => {
-                let time_passed = utils::current_unix() - *start;
+                let time_passed = utils::current_unix().saturating_sub(*start);
                 let intervals_passed = time_passed.as_secs() / interval.as_secs();
-                intervals_passed >= self.index as u64
+                if intervals_passed < self.index as u64 {
+                    return Verdict::Backlog;
+                }
             }
 
             DownloadMode::Standard {
@@ -215,21 +301,61 @@ impl Episode {
                 earliest_date,
             } => {
                 let max_time_exceeded = max_time.map_or(false, |max_time| {
-                    (utils::current_unix() - self.attrs.published) > max_time
+                    utils::current_unix().saturating_sub(self.attrs.published) > max_time
                 });
 
                 let max_episodes_exceeded = max_episodes.map_or(false, |max_episodes| {
-                    (episode_qty - max_episodes as usize) > self.index
+                    episode_qty.saturating_sub(max_episodes as usize) > self.index
                 });
 
                 let episode_too_old =
                     earliest_date.map_or(false, |date| date > self.attrs.published);
 
-                !max_time_exceeded && !max_episodes_exceeded && !episode_too_old
+                if max_time_exceeded {
+                    return Verdict::MaxDays;
+                }
+
+                if max_episodes_exceeded {
+                    return Verdict::MaxEpisodes;
+                }
+
+                if episode_too_old {
+                    return Verdict::EarliestDate;
+                }
             }
         };
 
-        passed_filter && !self.is_downloaded()
+        Verdict::Accepted
+    }
+
+    pub fn should_download(
+        &self,
+        mode: &DownloadMode,
+        episode_qty: usize,
+        tracker: &DownloadedEpisodes,
+    ) -> bool {
+        self.verdict(mode, episode_qty) == Verdict::Accepted && !self.is_downloaded(tracker)
+    }
+
+    /// Where the episode ends up once downloaded, named after the `name_pattern`.
+    pub fn file_path(&self, extension: Option<&OsStr>) -> PathBuf {
+        let mut name = sanitize_filename::sanitize(&self.config.name_pattern);
+
+        match extension {
+            Some(extension) => {
+                let max_file_len: usize = 255;
+                let ext_len = extension.len() + 1; // + 1 for the dot.
+                let overflow = (name.len() + ext_len).saturating_sub(max_file_len);
+                for _ in 0..overflow {
+                    name.pop();
+                }
+
+                let mut path = self.config.download_path.join(name);
+                path.set_extension(extension);
+                path
+            }
+            None => self.config.download_path.join(name),
+        }
     }
 
     /// Filename of episode when it's being downloaded.
@@ -238,11 +364,11 @@ impl Episode {
         format!("{}.partial", file_name)
     }
 
-    fn get_id(&self) -> String {
+    pub fn get_id(&self) -> String {
         self.config.id_pattern.replace(" ", "_")
     }
 
-    fn tracker_path(&self) -> &Path {
+    pub fn tracker_path(&self) -> &Path {
         self.config.tracker_path.as_path()
     }
 
@@ -250,15 +376,23 @@ impl Episode {
         DownloadedEpisode::new(self, path)
     }
 
+    /// Downloads the episode, with the enclosure transfer throttled by each of the rate limiters.
+    ///
+    /// Every attempt at the enclosure waits for a permit from the transfer limits, which is
+    /// given up again while waiting to retry.
     pub async fn download<'a>(
         &'a self,
         client: &reqwest::Client,
+        limits: &TransferLimits,
+        rate_limiters: &[&RateLimiter],
         ui: &DownloadBar,
     ) -> Result<DownloadedEpisode<'a>, String> {
         self.log_debug(ui, "downloading episode");
-        let audio_file = self.download_enclosure(client, ui).await?;
+        let audio_file = self
+            .download_enclosure(client, limits, rate_limiters, ui)
+            .await?;
         let mut episode = self.into_downloaded(audio_file);
-        episode.process(ui).await?;
+        episode.process(client, ui).await?;
         episode.run_download_hook(ui);
         episode.mark_downloaded()?;
         Ok(episode)
@@ -267,71 +401,217 @@ impl Episode {
     async fn download_enclosure<'a>(
         &'a self,
         client: &reqwest::Client,
+        limits: &TransferLimits,
+        rate_limiters: &[&RateLimiter],
         ui: &DownloadBar,
     ) -> Result<PathBuf, String> {
         let config = &self.config;
 
-        let partial_path = config
+        let partial_dir = config
             .partial_path
             .clone()
-            .unwrap_or_else(|| config.download_path.clone())
-            .join(self.partial_name());
+            .unwrap_or_else(|| config.download_path.clone());
+
+        utils::create_dir(&partial_dir);
+        utils::create_dir(&config.download_path);
+        let partial_path = partial_dir.join(self.partial_name());
+
+        // Every attempt resumes from whatever the previous ones left in the partial file.
+        let extension = {
+            let partial_path = &partial_path;
+            retry::with_retries(&config.download_retry, ui, move || async move {
+                let _permit = limits.acquire(self.attrs.url()).await;
+                self.fetch_enclosure(client, partial_path, rate_limiters, ui)
+                    .await
+            })
+            .await?
+        };
+
+        if config.verify_audio {
+            if let Err(e) = audio::verify(&partial_path) {
+                // Resuming a file that isn't audio won't turn it into one.
+                let _ = fs::remove_file(&partial_path);
+                ResumeMeta::remove(&partial_path);
+                return Err(e);
+            }
+        }
+
+        let path = {
+            let mut path = config.download_path.to_path_buf().join(self.partial_name());
+            path.set_extension(extension);
+            path
+        };
+
+        fs::rename(&partial_path, &path)
+            .map_err(|_| "failed to rename episode file".to_string())?;
+        ResumeMeta::remove(&partial_path);
+
+        Ok(path)
+    }
+
+    /// Appends the rest of the enclosure to the partial file, returning its extension.
+    ///
+    /// A partial file is only resumed if the server confirms the enclosure is unchanged,
+    /// otherwise it's truncated and the download starts over.
+    async fn fetch_enclosure(
+        &self,
+        client: &reqwest::Client,
+        partial_path: &Path,
+        rate_limiters: &[&RateLimiter],
+        ui: &DownloadBar,
+    ) -> Result<String, Failure> {
+        let url = self.as_ref().url();
 
         let mut file = fs::OpenOptions::new()
             .write(true)
             .create(true)
-            .open(&partial_path)
-            .map_err(|_| "failed to write file".to_string())?;
+            .open(partial_path)
+            .map_err(|_| Failure::permanent("failed to write file"))?;
 
         let mut downloaded = file
             .seek(std::io::SeekFrom::End(0))
-            .map_err(|_| "file error".to_string())?;
+            .map_err(|_| Failure::permanent("file error"))?;
 
-        self.log_trace(ui, format!("connecting to url: {:?}", self.as_ref().url()));
-        let response = client
-            .get(self.as_ref().url())
-            .header(reqwest::header::RANGE, format!("bytes={}-", downloaded))
-            .send()
-            .await;
+        let mut meta = ResumeMeta::load(partial_path).filter(|meta| meta.url == url);
+        let validator = meta
+            .as_ref()
+            .and_then(ResumeMeta::if_range)
+            .map(String::from);
 
-        let response = utils::short_handle_response(response)?;
+        if downloaded > 0 && validator.is_none() {
+            self.log_debug(ui, "partial file can't be validated, restarting download");
+            downloaded = restart_partial(&mut file, partial_path)?;
+        }
 
-        let total_size = response.content_length().unwrap_or(0);
-        let extension = utils::get_extension_from_response(&response, &self);
+        self.log_trace(ui, format!("connecting to url: {:?}", url));
+        let mut request = client.get(url);
 
-        ui.init_download_bar(downloaded, total_size);
+        if let (Some(validator), 1..) = (&validator, downloaded) {
+            self.log_debug(ui, format!("resuming download at byte {}", downloaded));
+            request = request
+                .header(reqwest::header::RANGE, format!("bytes={}-", downloaded))
+                .header(reqwest::header::IF_RANGE, validator);
+        }
+
+        let response = match request.send().await {
+            Err(e) if e.is_builder() => return Err(Failure::permanent("Invalid URL")),
+            response => utils::short_handle_response(response).map_err(Failure::transient)?,
+        };
+
+        let status = response.status();
+        let content_range = ContentRange::from_response(&response);
+
+        if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && downloaded > 0 {
+            // The previous attempt might have received everything but failed afterwards.
+            if content_range.and_then(|range| range.total) == Some(downloaded) {
+                let content_type = meta.and_then(|meta| meta.content_type);
+                return Ok(utils::get_extension(self, content_type.as_deref()));
+            }
+
+            restart_partial(&mut file, partial_path)?;
+            return Err(Failure::transient(
+                "partial file doesn't match the episode, restarting download",
+            ));
+        }
+
+        if !status.is_success() {
+            return Err(Failure::from_status(&response, &self.config.download_retry));
+        }
+
+        if status == reqwest::StatusCode::PARTIAL_CONTENT {
+            let start = content_range
+                .and_then(|range| range.range)
+                .map(|(start, _)| start);
+            if start != Some(downloaded) {
+                restart_partial(&mut file, partial_path)?;
+                return Err(Failure::transient(
+                    "server resumed at the wrong offset, restarting download",
+                ));
+            }
+        } else {
+            if downloaded > 0 {
+                self.log_debug(ui, "server can't resume the download, restarting");
+                downloaded = restart_partial(&mut file, partial_path)?;
+            }
+
+            let new_meta = ResumeMeta::from_response(url, &response);
+            new_meta.save(partial_path).map_err(Failure::permanent)?;
+            meta = Some(new_meta);
+        }
+
+        let expected = response.content_length().map(|len| downloaded + len);
+        let total_size = expected.or(self.attrs.length()).unwrap_or(0);
+        let content_type = meta.and_then(|meta| meta.content_type);
+        let extension = utils::get_extension(self, content_type.as_deref());
+
+        let mut transfer = ui.begin_transfer(downloaded, total_size);
 
         let mut stream = response.bytes_stream();
 
         while let Some(item) = stream.next().await {
-            let chunk = item.map_err(|_| "failed to load chunk".to_string())?;
+            let chunk = item.map_err(|_| Failure::transient("failed to load chunk"))?;
             file.write_all(&chunk)
-                .map_err(|_| "failed to write chunk to file".to_string())?;
-            downloaded = cmp::min(downloaded + (chunk.len() as u64), total_size);
-            ui.set_progress(downloaded);
+                .map_err(|_| Failure::permanent("failed to write chunk to file"))?;
+            downloaded += chunk.len() as u64;
+            transfer.set_progress(downloaded);
+
+            for limiter in rate_limiters {
+                limiter.throttle(chunk.len()).await;
+            }
         }
 
-        let path = {
-            let mut path = config
-                .download_path
-                .to_path_buf()
-                .join(&self.partial_name());
-            path.set_extension(extension);
-            path
-        };
+        drop(transfer);
 
-        fs::rename(partial_path, &path).map_err(|_| "failed to rename episode file".to_string())?;
+        match (expected, self.attrs.length()) {
+            (Some(expected), _) if downloaded < expected => {
+                return Err(Failure::transient(format!(
+                    "download ended early, received {} of {} bytes",
+                    downloaded, expected
+                )));
+            }
+            (Some(expected), _) if downloaded > expected => {
+                restart_partial(&mut file, partial_path)?;
+                return Err(Failure::transient(format!(
+                    "received {} bytes, more than the expected {}, restarting download",
+                    downloaded, expected
+                )));
+            }
+            (Some(expected), Some(length)) if expected != length => {
+                self.log_debug(
+                    ui,
+                    format!("feed states {} bytes, server sent {}", length, expected),
+                );
+            }
+            // Feeds often state a stale length, only the server can tell if the download is cut short.
+            (None, Some(length)) if downloaded != length => {
+                self.log_debug(
+                    ui,
+                    format!("feed states {} bytes, received {}", length, downloaded),
+                );
+            }
+            _ => {}
+        }
 
-        Ok(path)
+        Ok(extension)
     }
 }
 
+/// Empties the partial file and forgets what it was downloaded from.
+fn restart_partial(file: &mut fs::File, partial_path: &Path) -> Result<u64, Failure> {
+    ResumeMeta::remove(partial_path);
+    file.set_len(0)
+        .and_then(|_| file.seek(std::io::SeekFrom::Start(0)))
+        .map_err(|_| Failure::permanent("failed to truncate partial file"))
+}
+
 pub struct DownloadedEpisode<'a> {
     inner: &'a Episode,
     /// Where the episode is downloaded.
     path: PathBuf,
     /// The handle to the process of an optional post-download hook.
     handle: Option<JoinHandle<()>>,
+    /// Transcripts and chapters downloaded next to the episode.
+    sidecars: Vec<PathBuf>,
 }
 
 impl<'a> DownloadedEpisode<'a> {
@@ -340,6 +620,7 @@ impl<'a> DownloadedEpisode<'a> {
             inner,
             path,
             handle: None,
+            sidecars: vec![],
         }
     }
 
@@ -348,9 +629,7 @@ impl<'a> DownloadedEpisode<'a> {
     }
 
     pub fn mark_downloaded(&self) -> Result<(), String> {
-        let id = self.inner.config.id_pattern.replace(" ", "_");
-        let path = self.inner.config.tracker_path.as_path();
-        DownloadedEpisodes::append(&path, &id, self)
+        DownloadedEpisodes::append(self.inner.tracker_path(), self)
     }
 
     pub fn inner(&self) -> &Episode {
@@ -361,55 +640,229 @@ impl<'a> DownloadedEpisode<'a> {
         &self.path
     }
 
-    pub async fn normalize_id3v2(&self, ui: &DownloadBar) {
+    pub fn sidecars(&self) -> &[PathBuf] {
+        &self.sidecars
+    }
+
+    /// Path of a sidecar file, sharing the file stem of the episode.
+    fn sidecar_path(&self, extension: &str) -> PathBuf {
+        let stem = self.path.file_stem().unwrap().to_str().unwrap();
+        self.path.with_file_name(format!("{}.{}", stem, extension))
+    }
+
+    /// Downloads the configured transcript formats and the chapters file, if the feed has them.
+    ///
+    /// Failing to download these won't fail the episode itself.
+    async fn download_sidecars(&mut self, client: &reqwest::Client, ui: &DownloadBar) {
+        let config = &self.inner.config;
+        let mut downloads = vec![];
+
+        for transcript in self.inner.attrs.transcripts() {
+            let Some(format) = transcript.format() else {
+                self.inner
+                    .log_debug(ui, format!("unknown transcript type: {}", &transcript.mime));
+                continue;
+            };
+
+            let wanted = config.transcript_formats.iter().any(|f| f == format);
+            let path = self.sidecar_path(format);
+
+            // Only one transcript per format, as they'd otherwise share the same path.
+            if wanted && !downloads.iter().any(|(_, p)| p == &path) {
+                downloads.push((transcript.url, path));
+            }
+        }
+
+        if config.download_chapters {
+            if let Some(chapters) = self.inner.attrs.chapters() {
+                downloads.push((chapters.url, self.sidecar_path("chapters.json")));
+            }
+        }
+
+        for (url, path) in downloads {
+            self.inner
+                .log_debug(ui, format!("downloading sidecar file: {:?}", &path));
+            match utils::download_file(client, &url, &path).await {
+                Ok(()) => self.sidecars.push(path),
+                Err(e) => self
+                    .inner
+                    .log_warn(ui, format!("failed to download {}: {}", &url, e)),
+            }
+        }
+    }
+
+    pub async fn normalize_tags(&self, client: &reqwest::Client, ui: &DownloadBar) {
+        let Some(ext) = self.path.extension().and_then(|ext| ext.to_str()) else {
+            self.inner
+                .log_trace(ui, "skipping tag normalization: enclosure has no extension");
+            return;
+        };
+
+        match ext.to_lowercase().as_str() {
+            "mp3" => self.normalize_id3v2(client, ui).await,
+            "m4a" | "m4b" | "mp4" | "aac" | "ogg" | "oga" | "opus" | "flac" => {
+                self.normalize_lofty(ui).await
+            }
+            _ => self.inner.log_trace(
+                ui,
+                format!("skipping tag normalization: unsupported container: {}", ext),
+            ),
+        }
+    }
+
+    async fn normalize_id3v2(&self, client: &reqwest::Client, ui: &DownloadBar) {
         use id3::TagLike;
-        if self.path.extension().is_some_and(|ext| ext == "mp3") {
-            self.inner.log_trace(ui, "normalizing id3 tags");
-            if let Some(xml_tags) = &self.inner.tags {
-                let mut file_tags = id3::Tag::read_from_path(&self.path()).unwrap_or_default();
-
-                for frame in xml_tags.frames() {
-                    if !file_tags.get(frame.id()).is_some() {
-                        file_tags.add_frame(frame.to_owned());
-                        self.inner
-                            .log_trace(ui, format!("adding frame: {:?}", &frame));
-                    }
-                }
 
-                for (id, value) in &self.inner.config.id3_tags {
-                    file_tags.set_text(id, value);
-                }
+        self.inner.log_trace(ui, "normalizing id3 tags");
+        let xml_tags = self.inner.tags.to_id3();
+        let mut file_tags = id3::Tag::read_from_path(self.path()).unwrap_or_default();
 
-                if !file_tags
-                    .pictures()
-                    .any(|pic| pic.picture_type == id3::frame::PictureType::CoverFront)
-                {
-                    if let Some(img_url) = self.inner.image_url.as_ref() {
-                        if let Some(frame) =
-                            cache::get_image(img_url, id3::frame::PictureType::CoverFront, ui).await
-                        {
-                            file_tags.add_frame(frame);
-                            self.inner
-                                .log_debug(ui, "added cover image to podcast episode");
-                        } else {
-                            self.inner.log_warn(
-                                ui,
-                                format!("failed to fetch image from url: {:?}", img_url),
-                            );
-                        };
-                    }
-                }
+        for frame in xml_tags.frames() {
+            if file_tags.get(frame.id()).is_none() {
+                file_tags.add_frame(frame.to_owned());
+                self.inner
+                    .log_trace(ui, format!("adding frame: {:?}", &frame));
+            }
+        }
 
-                if let Err(e) = file_tags.write_to_path(&self.path(), id3::Version::Id3v24) {
-                    ui.log_error(format!("failed to write tags to file: {:?}", e));
+        for (id, value) in &self.inner.config.id3_tags {
+            file_tags.set_text(id, value);
+        }
+
+        if !file_tags
+            .pictures()
+            .any(|pic| pic.picture_type == id3::frame::PictureType::CoverFront)
+        {
+            if let Some(img_url) = self.inner.image_url.as_ref() {
+                if let Some(frame) =
+                    cache::get_image(img_url, id3::frame::PictureType::CoverFront, ui).await
+                {
+                    file_tags.add_frame(frame);
+                    self.inner
+                        .log_debug(ui, "added cover image to podcast episode");
+                } else {
+                    self.inner
+                        .log_warn(ui, format!("failed to fetch image from url: {:?}", img_url));
                 };
             }
-        } else {
-            self.inner
-                .log_trace(ui, "skipping id3 tag normalization: enclosure not an mp3");
+        }
+
+        if file_tags.chapters().next().is_none() {
+            self.add_chapters(&mut file_tags, client, ui).await;
+        }
+
+        if let Err(e) = file_tags.write_to_path(self.path(), id3::Version::Id3v24) {
+            ui.log_error(format!("failed to write tags to file: {:?}", e));
         };
     }
 
+    /// Tags MP4, Ogg and FLAC files, mapping the ID3v2 frame ids of `id3_tags` to
+    /// the equivalent keys of the container.
+    async fn normalize_lofty(&self, ui: &DownloadBar) {
+        use lofty::config::WriteOptions;
+        use lofty::file::AudioFile;
+        use lofty::file::TaggedFileExt;
+        use lofty::picture::{MimeType, Picture, PictureType};
+        use lofty::tag::{ItemKey, Tag, TagType};
+
+        self.inner.log_trace(ui, "normalizing tags");
+
+        let mut file = match lofty::read_from_path(self.path()) {
+            Ok(file) => file,
+            Err(e) => {
+                ui.log_error(format!("failed to read tags from file: {:?}", e));
+                return;
+            }
+        };
+
+        if file.primary_tag().is_none() {
+            file.insert_tag(Tag::new(file.primary_tag_type()));
+        }
+
+        let tag = file.primary_tag_mut().unwrap();
+
+        for (keys, value) in self.inner.tags.to_items() {
+            let Some(key) = keys
+                .into_iter()
+                .find(|key| key.map_key(tag.tag_type(), false).is_some())
+            else {
+                continue;
+            };
+
+            if tag.get_string(&key).is_none() {
+                self.inner
+                    .log_trace(ui, format!("adding item: {:?}: {}", &key, &value));
+                tag.insert_text(key, value);
+            }
+        }
+
+        for (id, value) in &self.inner.config.id3_tags {
+            let key = ItemKey::from_key(TagType::Id3v2, id);
+            let supported =
+                !matches!(key, ItemKey::Unknown(_)) && key.map_key(tag.tag_type(), false).is_some();
+
+            if supported {
+                tag.insert_text(key, value.clone());
+            } else {
+                self.inner.log_warn(
+                    ui,
+                    format!("no equivalent of id3 frame {} for {:?}", id, tag.tag_type()),
+                );
+            }
+        }
+
+        if !tag
+            .pictures()
+            .iter()
+            .any(|pic| pic.pic_type() == PictureType::CoverFront)
+        {
+            if let Some(img_url) = self.inner.image_url.as_ref() {
+                if let Some((data, mime)) = cache::get_image_data(img_url, ui).await {
+                    let mime = Some(MimeType::from_str(&mime));
+                    tag.push_picture(Picture::new_unchecked(
+                        PictureType::CoverFront,
+                        mime,
+                        None,
+                        data,
+                    ));
+                    self.inner
+                        .log_debug(ui, "added cover image to podcast episode");
+                } else {
+                    self.inner
+                        .log_warn(ui, format!("failed to fetch image from url: {:?}", img_url));
+                }
+            }
+        }
+
+        if let Err(e) = file.save_to_path(self.path(), WriteOptions::default()) {
+            ui.log_error(format!("failed to write tags to file: {:?}", e));
+        }
+    }
+
+    async fn add_chapters(&self, tags: &mut id3::Tag, client: &reqwest::Client, ui: &DownloadBar) {
+        use id3::TagLike;
+
+        let chapters = chapters::load(self, client, ui).await;
+        if chapters.is_empty() {
+            self.inner.log_trace(ui, "no chapters found");
+            return;
+        }
+
+        self.inner
+            .log_debug(ui, format!("adding {} chapters", chapters.len()));
+
+        // Plain seconds are parsed as numbers, so the duration isn't always a string.
+        let duration = self
+            .inner
+            .attrs
+            .get_text("itunes:duration")
+            .and_then(|duration| utils::parse_duration(&duration));
+
+        for frame in chapters::into_frames(chapters, duration, ui).await {
+            tags.add_frame(frame);
+        }
+    }
+
     fn file_name(&self) -> &str {
         self.path.file_name().unwrap().to_str().unwrap()
     }
@@ -461,35 +914,18 @@ impl<'a> DownloadedEpisode<'a> {
         Ok(())
     }
 
-    async fn process(&mut self, ui: &DownloadBar) -> Result<(), String> {
+    async fn process(&mut self, client: &reqwest::Client, ui: &DownloadBar) -> Result<(), String> {
         self.inner.log_debug(ui, "processing episode");
         self.rename()?;
         self.make_symlink(ui)?;
-        self.normalize_id3v2(ui).await;
+        self.download_sidecars(client, ui).await;
+        self.normalize_tags(client, ui).await;
 
         Ok(())
     }
 
     fn rename(&mut self) -> Result<(), String> {
-        let new_name = &self.inner.config.name_pattern;
-        let mut new_name = sanitize_filename::sanitize(new_name);
-
-        let new_path = match self.path.extension() {
-            Some(extension) => {
-                let max_file_len: usize = 255;
-                let ext_len = extension.len() + 1; // + 1 for the dot.
-                let overflow = (new_name.len() + ext_len).saturating_sub(max_file_len);
-                for _ in 0..overflow {
-                    new_name.pop();
-                }
-
-                let mut new_path = self.path.with_file_name(new_name);
-                new_path.set_extension(extension);
-                new_path
-            }
-            None => self.path.with_file_name(new_name),
-        };
-
+        let new_path = self.inner.file_path(self.path.extension());
         fs::rename(&self.path, &new_path).map_err(|_| "failed to rename episode".to_string())?;
         self.path = new_path;
         Ok(())
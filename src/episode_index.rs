@@ -0,0 +1,161 @@
+//! A local index of the known episodes of each podcast.
+//!
+//! It gets updated on every sync, so that the episodes of a podcast can be looked up
+//! without fetching and parsing its feed again.
+
+use crate::episode::Attributes;
+use crate::utils;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EpisodeStatus {
+    New,
+    Downloaded,
+    /// Downloaded, then deleted by the retention settings.
+    Deleted,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexEntry {
+    pub guid: String,
+    pub title: String,
+    /// Unix timestamp of the publication date.
+    pub published: u64,
+    pub url: String,
+    pub mime: Option<String>,
+    /// Size of the enclosure in bytes, as stated by the feed.
+    pub size: Option<u64>,
+    pub status: EpisodeStatus,
+}
+
+impl IndexEntry {
+    pub fn new(attrs: &Attributes, status: EpisodeStatus) -> Self {
+        Self {
+            guid: attrs.guid().to_string(),
+            title: attrs.title().to_string(),
+            published: attrs.published().as_secs(),
+            url: attrs.url().to_string(),
+            mime: attrs.mime().map(String::from),
+            size: attrs.length(),
+            status,
+        }
+    }
+}
+
+/// The known episodes of a podcast, ordered by publication date.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct EpisodeIndex {
+    episodes: Vec<IndexEntry>,
+}
+
+impl EpisodeIndex {
+    pub fn path(podcast: &str) -> PathBuf {
+        let file_name = format!("{}.json", sanitize_filename::sanitize(podcast));
+        utils::data_dir().join("index").join(file_name)
+    }
+
+    /// Loads the index of the podcast, an empty one is returned if it doesn't exist yet.
+    pub fn load(podcast: &str) -> Result<Self, String> {
+        let path = Self::path(podcast);
+
+        let s = match fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("failed to read episode index {:?}: {}", path, e)),
+        };
+
+        serde_json::from_str(&s).map_err(|e| format!("invalid episode index {:?}: {}", path, e))
+    }
+
+    pub fn save(&mut self, podcast: &str) -> Result<(), String> {
+        let path = Self::path(podcast);
+        self.episodes.sort_by_key(|entry| entry.published);
+
+        if let Some(parent) = path.parent() {
+            utils::create_dir(parent);
+        }
+
+        let s = serde_json::to_string(self).unwrap();
+        utils::write_atomic(&path, s)
+            .map_err(|e| format!("failed to write episode index {:?}: {}", path, e))
+    }
+
+    /// Moves the index along with a renamed podcast.
+    pub fn rename(podcast: &str, new_name: &str) -> Result<(), String> {
+        let (from, to) = (Self::path(podcast), Self::path(new_name));
+
+        match fs::rename(&from, &to) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("failed to move episode index {:?}: {}", from, e)),
+        }
+    }
+
+    pub fn remove(podcast: &str) -> Result<(), String> {
+        let path = Self::path(podcast);
+
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("failed to delete episode index {:?}: {}", path, e)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.episodes.is_empty()
+    }
+
+    /// Prints the episodes like `--episodes` does, newest first.
+    ///
+    /// Indices and verdicts depend on the feed, so they're left out.
+    pub fn print(&self) {
+        println!("INDEX  PUBLISHED         SIZE  STATUS      VERDICT                  TITLE");
+
+        for entry in self.episodes.iter().rev() {
+            let published = chrono::DateTime::from_timestamp(entry.published as i64, 0)
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+
+            let size = entry
+                .size
+                .map(|size| indicatif::HumanBytes(size).to_string())
+                .unwrap_or_else(|| "-".to_string());
+
+            let status = match entry.status {
+                EpisodeStatus::New => "new",
+                EpisodeStatus::Downloaded => "downloaded",
+                EpisodeStatus::Deleted => "deleted",
+            };
+
+            println!(
+                "{:>5}  {:<10}  {:>10}  {:<10}  {:<23}  {}",
+                "-", published, size, status, "-", entry.title
+            );
+        }
+    }
+
+    /// Inserts the entry, or replaces the one with the same guid.
+    pub fn upsert(&mut self, entry: IndexEntry) {
+        match self.episodes.iter_mut().find(|old| old.guid == entry.guid) {
+            Some(old) => *old = entry,
+            None => self.episodes.push(entry),
+        }
+    }
+
+    pub fn status(&self, guid: &str) -> Option<EpisodeStatus> {
+        self.episodes
+            .iter()
+            .find(|entry| entry.guid == guid)
+            .map(|entry| entry.status)
+    }
+
+    pub fn set_status(&mut self, guid: &str, status: EpisodeStatus) {
+        if let Some(entry) = self.episodes.iter_mut().find(|entry| entry.guid == guid) {
+            entry.status = status;
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Support for JSON Feed (<https://www.jsonfeed.org>).
+//!
+//! Like Atom feeds, JSON feeds are mapped onto the keys of their RSS 2.0 counterparts,
+//! while keeping the original fields so that patterns can refer to them directly.
+
+use crate::display::DownloadBar;
+use crate::episode::RawEpisode;
+use crate::podcast::RawPodcast;
+use crate::utils;
+use crate::utils::{insert_missing, map_text};
+use serde_json::Map;
+use serde_json::Value;
+
+pub fn parse(text: &str, ui: &DownloadBar) -> Option<(RawPodcast, Vec<RawEpisode>)> {
+    ui.log_info("parsing json feed");
+
+    let mut feed = match serde_json::from_str::<Value>(text) {
+        Ok(Value::Object(feed)) => feed,
+        Ok(_) => {
+            ui.log_error("json feed is not an object");
+            return None;
+        }
+        Err(e) => {
+            ui.log_error(format!("failed to parse json feed: {:?}", e));
+            return None;
+        }
+    };
+
+    let items = match feed.remove("items") {
+        Some(Value::Array(items)) => items,
+        _ => {
+            ui.log_error("json feed is missing its items");
+            return None;
+        }
+    };
+
+    map_feed(&mut feed);
+
+    let episodes = items
+        .into_iter()
+        .filter_map(|item| match item {
+            Value::Object(mut item) => {
+                map_item(&mut item);
+                Some(RawEpisode::new(item))
+            }
+            _ => None,
+        })
+        .collect();
+
+    Some((RawPodcast::new(feed), episodes))
+}
+
+fn map_feed(feed: &mut Map<String, Value>) {
+    if let Some(link) = map_text(feed, "home_page_url") {
+        insert_missing(feed, "link", link);
+    }
+
+    if let Some(author) = author_name(feed) {
+        insert_missing(feed, "itunes:author", author);
+    }
+
+    if let Some(image) = map_text(feed, "icon").or_else(|| map_text(feed, "favicon")) {
+        insert_missing(feed, "image", image);
+    }
+}
+
+fn map_item(item: &mut Map<String, Value>) {
+    // Titles are optional in JSON Feed, items without one are named after their text.
+    let title = map_text(item, "content_text")
+        .and_then(|text| Some(text.trim().lines().next()?.trim().to_string()))
+        .filter(|title| !title.is_empty())
+        .or_else(|| map_text(item, "url"));
+
+    if let Some(title) = title {
+        insert_missing(item, "title", title);
+    }
+
+    if let Some(id) = item.get("id").and_then(utils::val_to_string) {
+        insert_missing(item, "guid", id);
+    }
+
+    let date = map_text(item, "date_published").or_else(|| map_text(item, "date_modified"));
+    if let Some(date) = date {
+        insert_missing(item, "pubDate", date);
+    }
+
+    let description = map_text(item, "summary")
+        .or_else(|| map_text(item, "content_text"))
+        .or_else(|| map_text(item, "content_html"));
+
+    if let Some(description) = description {
+        insert_missing(item, "description", description);
+    }
+
+    if let Some(author) = author_name(item) {
+        insert_missing(item, "author", author);
+    }
+
+    if let Some(image) = map_text(item, "image").or_else(|| map_text(item, "banner_image")) {
+        insert_missing(item, "itunes:image", image);
+    }
+
+    if let Some(enclosure) = enclosure(item) {
+        insert_missing(item, "enclosure", enclosure);
+    }
+
+    let duration = item
+        .get("attachments")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .find_map(|attachment| attachment.get("duration_in_seconds")?.as_u64());
+
+    if let Some(duration) = duration {
+        insert_missing(item, "itunes:duration", duration.to_string());
+    }
+}
+
+/// Converts the first audio attachment, or failing that the first attachment,
+/// into the attributes of an RSS `<enclosure>` element.
+fn enclosure(item: &Map<String, Value>) -> Option<Value> {
+    let attachments = item.get("attachments")?.as_array()?;
+
+    let is_audio = |attachment: &&Value| {
+        attachment
+            .get("mime_type")
+            .and_then(Value::as_str)
+            .is_some_and(|mime| mime.starts_with("audio/"))
+    };
+
+    let attachment = attachments
+        .iter()
+        .find(is_audio)
+        .or_else(|| attachments.first())?;
+
+    let mut enclosure = Map::new();
+    enclosure.insert("@url".to_string(), attachment.get("url")?.clone());
+
+    if let Some(mime) = attachment.get("mime_type") {
+        enclosure.insert("@type".to_string(), mime.clone());
+    }
+
+    if let Some(length) = attachment.get("size_in_bytes") {
+        enclosure.insert("@length".to_string(), length.clone());
+    }
+
+    Some(Value::Object(enclosure))
+}
+
+/// Version 1.1 uses an `authors` array, version 1.0 a single `author` object.
+fn author_name(map: &Map<String, Value>) -> Option<String> {
+    let author = match map.get("authors") {
+        Some(authors) => authors.as_array()?.first()?,
+        None => map.get("author")?,
+    };
+
+    map_text(author.as_object()?, "name")
+}
@@ -0,0 +1,110 @@
+//! An advisory lock that keeps several TaleCast processes from writing to the same
+//! trackers, partial files and configs at the same time.
+//!
+//! The lock is held for as long as the process runs, and released by the OS when it exits,
+//! so a crashed process never leaves a stale lock behind. The daemon is the exception, it
+//! only holds the lock while syncing, see [`SharedLock`].
+
+use crate::utils;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+pub struct ProcessLock {
+    _file: fs::File,
+}
+
+impl ProcessLock {
+    pub fn path() -> PathBuf {
+        utils::data_dir().join("talecast.lock")
+    }
+
+    /// Takes the lock, waiting for other processes to release it if `wait` is set.
+    pub fn acquire(wait: bool) -> Result<Self, String> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            utils::create_dir(parent);
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| format!("failed to open lock file {:?}: {}", path, e))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(fs::TryLockError::WouldBlock) if wait => {
+                eprintln!("waiting for another talecast process to finish..");
+                file.lock()
+                    .map_err(|e| format!("failed to lock {:?}: {}", path, e))?;
+            }
+            Err(fs::TryLockError::WouldBlock) => {
+                let pid = fs::read_to_string(&path).unwrap_or_default();
+                return Err(format!(
+                    "another talecast process is running (pid {}), use --wait to wait for it to finish",
+                    pid.trim()
+                ));
+            }
+            Err(fs::TryLockError::Error(e)) => {
+                return Err(format!("failed to lock {:?}: {}", path, e));
+            }
+        }
+
+        // The pid is only informational, the lock itself is what counts.
+        let _ = file
+            .set_len(0)
+            .and_then(|_| write!(file, "{}", std::process::id()));
+
+        Ok(Self { _file: file })
+    }
+}
+
+/// The lock of a process that syncs now and then, shared by its concurrent syncs.
+///
+/// It's taken when the first sync starts and released when the last one finishes,
+/// so other processes can run in between.
+#[derive(Default)]
+pub struct SharedLock {
+    held: Mutex<Option<(ProcessLock, usize)>>,
+    /// Only one sync waits for the lock, the others wait for that one.
+    acquiring: tokio::sync::Mutex<()>,
+}
+
+impl SharedLock {
+    pub async fn hold(self: &Arc<Self>) -> Result<SharedLockGuard, String> {
+        let _acquiring = self.acquiring.lock().await;
+
+        if let Some((_, holders)) = self.held.lock().unwrap().as_mut() {
+            *holders += 1;
+            return Ok(SharedLockGuard(Arc::clone(self)));
+        }
+
+        let lock = tokio::task::spawn_blocking(|| ProcessLock::acquire(true))
+            .await
+            .map_err(|e| format!("failed to wait for lock: {}", e))??;
+
+        *self.held.lock().unwrap() = Some((lock, 1));
+        Ok(SharedLockGuard(Arc::clone(self)))
+    }
+}
+
+/// Releases the [`SharedLock`] when the last sync holding it is done.
+pub struct SharedLockGuard(Arc<SharedLock>);
+
+impl Drop for SharedLockGuard {
+    fn drop(&mut self) {
+        let mut held = self.0.held.lock().unwrap();
+
+        if let Some((_, holders)) = held.as_mut() {
+            *holders -= 1;
+            if *holders == 0 {
+                *held = None;
+            }
+        }
+    }
+}
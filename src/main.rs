@@ -1,18 +1,36 @@
 use crate::config::GlobalConfig;
 use crate::config::PodcastConfigs;
+use crate::display::DownloadBar;
+use crate::lock::ProcessLock;
+use crate::selector::EpisodeSelector;
 use clap::Parser;
 use regex::Regex;
 use std::path::PathBuf;
 
+mod atom;
+mod audio;
 mod cache;
+mod chapters;
 mod config;
+mod daemon;
 mod display;
 mod download_tracker;
 mod episode;
+mod episode_index;
+mod json_feed;
+mod lock;
 mod opml;
+mod partial;
 mod patterns;
 mod podcast;
+mod podcast_ns;
+mod redirects;
+mod relocate;
+mod retention;
+mod retry;
+mod selector;
 mod tags;
+mod transfer;
 mod utils;
 
 pub const APPNAME: &'static str = "talecast";
@@ -49,6 +67,20 @@ struct Args {
     catch_up: bool,
     #[arg(short, long, num_args = 1..=2, value_names = &["URL", "NAME"], help = "Add new podcast")]
     add: Vec<String>,
+    #[arg(long, value_name = "NAME", help = "Remove a podcast")]
+    remove: Option<String>,
+    #[arg(long, num_args = 2, value_names = &["NAME", "NEW_NAME"], help = "Rename a podcast")]
+    rename: Vec<String>,
+    #[arg(
+        long,
+        help = "Delete the download directory and tracker of a removed podcast"
+    )]
+    delete_files: bool,
+    #[arg(
+        long,
+        help = "Move the download directory, tracker and symlinks of a renamed podcast"
+    )]
+    move_files: bool,
     #[arg(
         short,
         long,
@@ -69,10 +101,120 @@ struct Args {
     search: Option<Vec<String>>,
     #[arg(long, help = "Print your podcasts to stdout")]
     list: bool,
+    #[arg(
+        long,
+        help = "Print the episodes a sync would download without downloading them"
+    )]
+    dry_run: bool,
+    #[arg(
+        long,
+        help = "Keep running and sync each podcast on its refresh interval"
+    )]
+    daemon: bool,
+    #[arg(
+        long,
+        help = "Wait for another running talecast process to finish instead of exiting"
+    )]
+    wait: bool,
+    #[arg(long, help = "Update the urls of feeds that have moved while syncing")]
+    update_urls: bool,
+    #[arg(long, help = "Convert the download trackers to the JSON format")]
+    migrate_trackers: bool,
+    #[arg(
+        long,
+        value_name = "OLD_PATTERN",
+        help = "Rewrite the tracker ids from a previous id_pattern to the current one, preview with --dry-run"
+    )]
+    migrate_ids: Option<String>,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Print the episodes of a podcast and whether they'd be downloaded"
+    )]
+    episodes: Option<String>,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Download the episodes of a podcast chosen with --guid, --index, --title, --after and --before"
+    )]
+    download: Option<String>,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Mark the selected episodes of a podcast as downloaded without downloading them"
+    )]
+    mark: Option<String>,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Remove the selected episodes of a podcast from its tracker, so they're downloaded again"
+    )]
+    unmark: Option<String>,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Print the tracked episodes of a podcast and when they were downloaded"
+    )]
+    tracker: Option<String>,
+    #[arg(long, value_name = "GUID", help = "Select an episode by its guid")]
+    guid: Vec<String>,
+    #[arg(
+        long,
+        value_name = "INDEX",
+        help = "Select an episode by its index, as shown by --episodes"
+    )]
+    index: Vec<usize>,
+    #[arg(
+        long,
+        value_name = "REGEX",
+        help = "Select episodes with a title matching a regex pattern"
+    )]
+    title: Option<String>,
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "Select episodes published after a date"
+    )]
+    after: Option<String>,
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "Select episodes published before a date"
+    )]
+    before: Option<String>,
 }
 
 impl From<Args> for Action {
     fn from(args: Args) -> Self {
+        // Only parsed when episodes are selected, an invalid selection shouldn't stop a sync.
+        let selector = || {
+            let title = args.title.as_ref().map(|title| {
+                let title = format!("(?i){}", title); // Case insensitive
+                Regex::new(&title).unwrap_or_else(|e| {
+                    eprintln!("invalid title pattern: {}", e);
+                    std::process::exit(1);
+                })
+            });
+
+            let date = |date: &Option<String>| {
+                date.as_ref()
+                    .map(|date| utils::date_str_to_unix(date))
+                    .transpose()
+                    .unwrap_or_else(|e| {
+                        eprintln!("{}", e);
+                        std::process::exit(1);
+                    })
+            };
+
+            EpisodeSelector {
+                guids: args.guid.clone(),
+                indices: args.index.clone(),
+                title,
+                after: date(&args.after),
+                before: date(&args.before),
+            }
+        };
+
         let filter = args.filter.map(|filter| {
             let filter = format!("(?i){}", filter); // Case insensitive
             Regex::new(&filter).unwrap()
@@ -85,6 +227,52 @@ impl From<Args> for Action {
             return Self::List { filter };
         }
 
+        if let Some(name) = args.episodes {
+            return Self::Episodes { name };
+        }
+
+        if let Some(name) = args.download {
+            let selector = selector();
+            if selector.is_empty() {
+                eprintln!("select episodes to download with --guid, --index, --title, --after or --before");
+                std::process::exit(1);
+            }
+
+            return Self::Download {
+                name,
+                selector,
+                print,
+            };
+        }
+
+        if let Some(name) = args.mark {
+            let selector = selector();
+            if selector.is_empty() {
+                eprintln!(
+                    "select episodes to mark with --guid, --index, --title, --after or --before"
+                );
+                std::process::exit(1);
+            }
+
+            return Self::Mark { name, selector };
+        }
+
+        if let Some(name) = args.unmark {
+            let selector = selector();
+            if selector.is_empty() {
+                eprintln!(
+                    "select episodes to unmark with --guid, --index, --title, --after or --before"
+                );
+                std::process::exit(1);
+            }
+
+            return Self::Unmark { name, selector };
+        }
+
+        if let Some(name) = args.tracker {
+            return Self::Tracker { name };
+        }
+
         if args.edit_config {
             let path = GlobalConfig::default_path();
             return Self::Edit { path };
@@ -119,11 +307,50 @@ impl From<Args> for Action {
             };
         }
 
+        if let Some(name) = args.remove {
+            return Self::Remove {
+                name,
+                delete_files: args.delete_files,
+            };
+        }
+
+        if let [name, new_name] = args.rename.as_slice() {
+            return Self::Rename {
+                name: name.clone(),
+                new_name: new_name.clone(),
+                move_files: args.move_files,
+            };
+        }
+
         if catch_up {
             return Self::CatchUp { filter };
         }
 
-        Self::Sync { filter, print }
+        if args.migrate_trackers {
+            return Self::MigrateTrackers { filter };
+        }
+
+        if let Some(old_pattern) = args.migrate_ids {
+            return Self::MigrateIds {
+                filter,
+                old_pattern,
+                dry_run: args.dry_run,
+            };
+        }
+
+        if args.daemon {
+            return Self::Daemon {
+                filter,
+                update_urls: args.update_urls,
+            };
+        }
+
+        Self::Sync {
+            filter,
+            print,
+            dry_run: args.dry_run,
+            update_urls: args.update_urls,
+        }
     }
 }
 
@@ -131,6 +358,25 @@ enum Action {
     List {
         filter: Option<Regex>,
     },
+    Episodes {
+        name: String,
+    },
+    Download {
+        name: String,
+        selector: EpisodeSelector,
+        print: bool,
+    },
+    Mark {
+        name: String,
+        selector: EpisodeSelector,
+    },
+    Unmark {
+        name: String,
+        selector: EpisodeSelector,
+    },
+    Tracker {
+        name: String,
+    },
     CatchUp {
         filter: Option<Regex>,
     },
@@ -154,12 +400,53 @@ enum Action {
         query: String,
         catch_up: bool,
     },
+    Remove {
+        name: String,
+        delete_files: bool,
+    },
+    Rename {
+        name: String,
+        new_name: String,
+        move_files: bool,
+    },
     Sync {
         filter: Option<Regex>,
         print: bool,
+        dry_run: bool,
+        update_urls: bool,
+    },
+    Daemon {
+        filter: Option<Regex>,
+        update_urls: bool,
+    },
+    MigrateTrackers {
+        filter: Option<Regex>,
+    },
+    MigrateIds {
+        filter: Option<Regex>,
+        old_pattern: String,
+        dry_run: bool,
     },
 }
 
+impl Action {
+    /// Whether the action writes to the trackers, partial files or configs,
+    /// and so may not run alongside another process doing the same.
+    fn needs_lock(&self) -> bool {
+        match self {
+            Self::List { .. }
+            | Self::Export { .. }
+            | Self::Edit { .. }
+            | Self::Episodes { .. }
+            | Self::Tracker { .. } => false,
+            // Only locks while syncing, so other processes can run between its syncs.
+            Self::Daemon { .. } => false,
+            Self::Sync { dry_run, .. } | Self::MigrateIds { dry_run, .. } => !dry_run,
+            _ => true,
+        }
+    }
+}
+
 use chrono::Local;
 use fern::Dispatch;
 
@@ -188,16 +475,34 @@ fn setup_logging(config: &config::LogConfig) -> Result<PathBuf, fern::InitError>
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
+    let config_path = args.config.clone();
+    let dry_run = args.dry_run;
+    let wait = args.wait;
+    let action = Action::from(args);
+
+    // Held until the process exits.
+    let _lock = match action.needs_lock() {
+        true => match ProcessLock::acquire(wait) {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        false => None,
+    };
 
-    let global_config = match args.config.as_ref() {
-        Some(path) => GlobalConfig::load_from_path(path),
+    let global_config = match config_path {
+        Some(path) => GlobalConfig::load_from_path(&path),
+        // Loading the default config saves it, which a dry run shouldn't do.
+        None if dry_run => GlobalConfig::load_from_path(&GlobalConfig::default_path()),
         None => GlobalConfig::load(),
     };
 
     let log_path = setup_logging(&global_config.log()).unwrap();
 
-    match Action::from(args) {
-        Action::Import { path, catch_up } => opml::import(&path, catch_up),
+    match action {
+        Action::Import { path, catch_up } => opml::import(&path, catch_up, &global_config).await,
 
         Action::Edit { path } => utils::edit_file(&path),
 
@@ -209,6 +514,30 @@ async fn main() {
             }
         }
 
+        Action::Episodes { name } => {
+            PodcastConfigs::load()
+                .print_episodes(&name, global_config)
+                .await
+        }
+
+        Action::Mark { name, selector } => {
+            PodcastConfigs::load()
+                .mark(&name, selector, global_config)
+                .await
+        }
+
+        Action::Unmark { name, selector } => {
+            PodcastConfigs::load()
+                .unmark(&name, selector, global_config)
+                .await
+        }
+
+        Action::Tracker { name } => {
+            PodcastConfigs::load()
+                .print_tracker(&name, global_config)
+                .await
+        }
+
         Action::Search { query, catch_up } => {
             utils::search_podcasts(&global_config, query, catch_up).await
         }
@@ -220,7 +549,21 @@ async fn main() {
             url,
             catch_up,
         } => {
-            let name = match name {
+            let client = config::init_reqwest_client(&global_config);
+            let ui = DownloadBar::hidden(url.clone(), global_config.style());
+
+            let summary = match podcast::fetch_feed(&client, &url, &global_config, &ui).await {
+                Ok(summary) => summary,
+                Err(e) => {
+                    eprintln!("error: {}: {}", url, e);
+                    std::process::exit(1);
+                }
+            };
+
+            summary.print();
+
+            // The channel title is only a default, an explicit name wins.
+            let name = match name.or(summary.title) {
                 Some(name) => name,
                 None => match utils::get_input(Some("enter name of podcast: ")) {
                     Some(name) => name,
@@ -234,29 +577,100 @@ async fn main() {
                 eprintln!("'{}' added!", name);
                 if catch_up {
                     // Matches only the added podcast.
-                    let filter = Regex::new(&format!("^{}$", &name)).unwrap();
-                    config::PodcastConfigs::catch_up(Some(filter));
+                    match Regex::new(&format!("^{}$", regex::escape(&name))) {
+                        Ok(filter) => config::PodcastConfigs::catch_up(Some(filter)),
+                        Err(e) => eprintln!("error: failed to catch up '{}': {}", name, e),
+                    }
                 }
             } else {
                 eprintln!("'{}' already exists!", name);
             }
         }
 
-        Action::Sync { filter, print } => {
+        Action::Remove { name, delete_files } => {
+            PodcastConfigs::load()
+                .remove(&name, delete_files, global_config)
+                .await
+        }
+
+        Action::Rename {
+            name,
+            new_name,
+            move_files,
+        } => {
+            PodcastConfigs::load()
+                .rename(&name, &new_name, move_files, global_config)
+                .await
+        }
+
+        Action::Download {
+            name,
+            selector,
+            print,
+        } => {
+            let paths = PodcastConfigs::load()
+                .download(&name, selector, global_config)
+                .await;
+
+            eprintln!("{} episodes downloaded.", paths.len());
+            print_paths(paths, print);
+        }
+
+        Action::Sync {
+            filter,
+            print,
+            dry_run,
+            update_urls,
+        } => {
             let paths = PodcastConfigs::load()
                 .assert_not_empty()
                 .filter(filter)
-                .sync(global_config, &log_path)
+                .sync(global_config, &log_path, dry_run, update_urls)
                 .await;
 
+            if dry_run {
+                eprintln!("Dry run complete!");
+                eprintln!("{} episodes would be downloaded.", paths.len());
+                return;
+            }
+
             eprintln!("Syncing complete!");
             eprintln!("{} episodes downloaded.", paths.len());
+            print_paths(paths, print);
+        }
 
-            if print {
-                for path in paths {
-                    println!("{}", path.to_str().unwrap());
-                }
-            }
+        Action::MigrateTrackers { filter } => {
+            PodcastConfigs::load()
+                .filter(filter)
+                .migrate_trackers(global_config)
+                .await
+        }
+
+        Action::MigrateIds {
+            filter,
+            old_pattern,
+            dry_run,
+        } => {
+            PodcastConfigs::load()
+                .filter(filter)
+                .migrate_ids(&old_pattern, global_config, dry_run)
+                .await
+        }
+
+        Action::Daemon {
+            filter,
+            update_urls,
+        } => {
+            let podcasts = PodcastConfigs::load().assert_not_empty().filter(filter);
+            daemon::run(podcasts, global_config, update_urls).await;
+        }
+    }
+}
+
+fn print_paths(paths: Vec<PathBuf>, print: bool) {
+    if print {
+        for path in paths {
+            println!("{}", path.to_str().unwrap());
         }
     }
 }
@@ -1,5 +1,9 @@
 use crate::config;
-use crate::config::PodcastConfig;
+use crate::config::{GlobalConfig, PodcastConfig};
+use crate::display::DownloadBar;
+use crate::podcast;
+use futures::stream;
+use futures::StreamExt;
 use opml::OPML;
 use regex::Regex;
 use std::collections::HashMap;
@@ -25,7 +29,11 @@ pub async fn export(p: &Path, filter: Option<Regex>) {
         .unwrap();
 }
 
-pub fn import(p: &Path, catch_up: bool) {
+/// Imports the podcasts of the OPML file.
+///
+/// The feeds are checked first, podcasts with a broken feed are imported all the same
+/// but get a warning.
+pub async fn import(p: &Path, catch_up: bool, global_config: &GlobalConfig) {
     let opml_string = std::fs::read_to_string(p).unwrap();
     let opml = opml::OPML::from_str(&opml_string).unwrap();
 
@@ -73,7 +81,31 @@ pub fn import(p: &Path, catch_up: bool) {
 
     if podcasts.is_empty() {
         eprintln!("no podcasts found.");
-    } else {
-        config::PodcastConfigs::extend(podcasts);
+        return;
     }
+
+    let client = config::init_reqwest_client(global_config);
+    let mut results: Vec<_> = stream::iter(&podcasts)
+        .map(|(name, podcast)| {
+            let client = &client;
+            async move {
+                let ui = DownloadBar::hidden(name.clone(), global_config.style());
+                let result = podcast::fetch_feed(client, &podcast.url, global_config, &ui).await;
+                (name, result)
+            }
+        })
+        .buffer_unordered(8)
+        .collect()
+        .await;
+
+    results.sort_by_key(|(name, _)| *name);
+
+    for (name, result) in results {
+        match result {
+            Ok(summary) => eprintln!("{}: {} episodes", name, summary.episodes),
+            Err(e) => eprintln!("warning: {}: {}", name, e),
+        }
+    }
+
+    config::PodcastConfigs::extend(podcasts);
 }
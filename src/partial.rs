@@ -0,0 +1,105 @@
+//! Resuming partially downloaded episodes.
+//!
+//! Next to every `.partial` file lies a `.partial.meta` file, recording what the partial
+//! data was downloaded from. It's sent back as `If-Range`, so that the server only continues
+//! the download if the enclosure hasn't changed in the meantime.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResumeMeta {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_type: Option<String>,
+}
+
+impl ResumeMeta {
+    pub fn from_response(url: &str, response: &reqwest::Response) -> Self {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+
+        Self {
+            url: url.to_string(),
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+            content_type: header(reqwest::header::CONTENT_TYPE),
+        }
+    }
+
+    fn path(partial_path: &Path) -> PathBuf {
+        let mut path = partial_path.as_os_str().to_owned();
+        path.push(".meta");
+        PathBuf::from(path)
+    }
+
+    pub fn load(partial_path: &Path) -> Option<Self> {
+        let s = fs::read_to_string(Self::path(partial_path)).ok()?;
+        serde_json::from_str(&s).ok()
+    }
+
+    pub fn save(&self, partial_path: &Path) -> Result<(), String> {
+        let s = serde_json::to_string(self).unwrap();
+        fs::write(Self::path(partial_path), s).map_err(|_| "failed to write resume metadata".into())
+    }
+
+    pub fn remove(partial_path: &Path) {
+        let _ = fs::remove_file(Self::path(partial_path));
+    }
+
+    /// The validator for the `If-Range` header.
+    ///
+    /// Weak ETags aren't allowed there, so the last-modified date is used instead.
+    pub fn if_range(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+}
+
+/// A `Content-Range` header, such as `bytes 100-199/1000` or `bytes */1000`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContentRange {
+    pub range: Option<(u64, u64)>,
+    pub total: Option<u64>,
+}
+
+impl ContentRange {
+    pub fn from_response(response: &reqwest::Response) -> Option<Self> {
+        let value = response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)?
+            .to_str()
+            .ok()?;
+
+        Self::parse(value)
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+
+        let range = match range {
+            "*" => None,
+            range => {
+                let (start, end) = range.split_once('-')?;
+                Some((start.parse().ok()?, end.parse().ok()?))
+            }
+        };
+
+        let total = match total {
+            "*" => None,
+            total => Some(total.parse().ok()?),
+        };
+
+        Some(Self { range, total })
+    }
+}
@@ -50,18 +50,9 @@ impl FullPattern {
         Self::from_str(s).evaluate(data)
     }
 
-    pub fn direct_eval_file(s: &str, data: EvalData<'_>) -> PathBuf {
-        let p = PathBuf::from(Self::direct_eval(s, data));
-        if let Some(path) = p.parent() {
-            utils::create_dir(&path);
-        }
-        p
-    }
-
-    pub fn direct_eval_dir(s: &str, data: EvalData<'_>) -> PathBuf {
-        let p = PathBuf::from(Self::direct_eval(s, data));
-        utils::create_dir(&p);
-        p
+    /// Directories aren't created here, but when something is written to them.
+    pub fn direct_eval_path(s: &str, data: EvalData<'_>) -> PathBuf {
+        PathBuf::from(Self::direct_eval(s, data))
     }
 }
 
@@ -146,12 +137,16 @@ impl Evaluate for DataPattern {
             Ty::RssEpisode => {
                 let key = &self.data;
 
-                data.episode.get_str(&key).unwrap_or(null).to_string()
+                data.episode
+                    .get_text(key)
+                    .unwrap_or_else(|| null.to_string())
             }
             Ty::RssChannel => {
                 let key = &self.data;
 
-                data.podcast.get_str(&key).unwrap_or(null).to_string()
+                data.podcast
+                    .get_text(key)
+                    .unwrap_or_else(|| null.to_string())
             }
         }
     }
@@ -187,6 +182,11 @@ enum UnitPattern {
     PodName,
     AppName,
     Home,
+    Season,
+    Episode,
+    Location,
+    Persons,
+    Value,
 }
 
 impl UnitPattern {
@@ -197,6 +197,11 @@ impl UnitPattern {
             "podname" => Self::PodName,
             "appname" => Self::AppName,
             "home" => Self::Home,
+            "season" => Self::Season,
+            "episode" => Self::Episode,
+            "location" => Self::Location,
+            "persons" => Self::Persons,
+            "value" => Self::Value,
             _ => return None,
         }
         .into()
@@ -211,6 +216,48 @@ impl Evaluate for UnitPattern {
             Self::PodName => data.pod_name.to_string(),
             Self::AppName => crate::APPNAME.to_string(),
             Self::Home => home().unwrap_or("<missing home>".to_string()),
+            Self::Season => match data.episode.season() {
+                Some(season) => season.number.to_string(),
+                None => "<missing season>".to_string(),
+            },
+            Self::Episode => match data.episode.episode_number() {
+                Some(episode) => episode.number,
+                None => "<missing episode>".to_string(),
+            },
+            Self::Location => match data.episode.location() {
+                Some(location) => location.name,
+                None => "<missing location>".to_string(),
+            },
+            Self::Persons => {
+                let persons = data.episode.persons();
+                if persons.is_empty() {
+                    "<missing persons>".to_string()
+                } else {
+                    persons
+                        .into_iter()
+                        .map(|person| person.name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            }
+            Self::Value => {
+                let recipients = data
+                    .episode
+                    .value()
+                    .or_else(|| data.podcast.value())
+                    .map(|value| value.recipients)
+                    .unwrap_or_default();
+
+                if recipients.is_empty() {
+                    "<missing value>".to_string()
+                } else {
+                    recipients
+                        .into_iter()
+                        .map(|recipient| recipient.name.unwrap_or(recipient.address))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            }
         }
     }
 }
@@ -1,69 +1,176 @@
+use crate::atom;
+use crate::cache;
 use crate::config::DownloadMode;
 use crate::config::EvalData;
 use crate::config::PodcastConfig;
+use crate::config::PodcastConfigs;
 use crate::config::{Config, GlobalConfig};
 use crate::display::DownloadBar;
+use crate::download_tracker::DownloadedEpisodes;
+use crate::download_tracker::TrackerEntry;
 use crate::episode;
 use crate::episode::Episode;
 use crate::episode::RawEpisode;
+use crate::episode_index::{EpisodeIndex, EpisodeStatus, IndexEntry};
+use crate::json_feed;
+use crate::patterns::Evaluate;
+use crate::patterns::FullPattern;
+use crate::podcast_ns;
+use crate::relocate::{self, PathKind, Relocation};
+use crate::retention::{Retention, StoredEpisode};
+use crate::selector::EpisodeSelector;
 use crate::tags;
+use crate::transfer::RateLimiter;
+use crate::transfer::TransferLimits;
 use crate::utils;
+use futures::stream;
+use futures::StreamExt;
 use quickxml_to_serde::{xml_string_to_json, Config as XmlConfig};
 use serde_json::Map;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time;
 
-fn get_inner_channel(xml: String) -> Option<serde_json::Value> {
+/// The top-level element holding the podcast and its episodes.
+enum FeedRoot {
+    /// The `rss/channel` element of an RSS 2.0 feed.
+    Rss(Value),
+    /// The `feed` element of an Atom feed.
+    Atom(Value),
+}
+
+impl FeedRoot {
+    fn items_key(&self) -> &'static str {
+        match self {
+            Self::Rss(_) => "item",
+            Self::Atom(_) => "entry",
+        }
+    }
+
+    fn into_inner(self) -> Value {
+        match self {
+            Self::Rss(val) | Self::Atom(val) => val,
+        }
+    }
+}
+
+fn get_feed_root(xml: String) -> Option<FeedRoot> {
     let conf = XmlConfig::new_with_defaults();
-    std::mem::take(
-        xml_string_to_json(xml, &conf)
-            .ok()?
-            .get_mut("rss")?
-            .get_mut("channel")?,
-    )
-    .into()
+    let mut root = xml_string_to_json(xml, &conf).ok()?;
+
+    if let Some(channel) = root.get_mut("rss").and_then(|rss| rss.get_mut("channel")) {
+        return Some(FeedRoot::Rss(std::mem::take(channel)));
+    }
+
+    let feed = root.get_mut("feed")?;
+    Some(FeedRoot::Atom(std::mem::take(feed)))
+}
+
+/// Namespaces whose elements are kept as separate `namespace:key` keys.
+const PRESERVED_NAMESPACES: [&str; 4] = ["itunes", "podcast", "psc", "sy"];
+
+const NAMESPACE_PLACEHOLDER: &str = "__placeholder__";
+
+/// Renames the preserved namespaced tags so that the xml library won't merge them.
+///
+/// Only tags are replaced, so that text such as "this podcast: episode 2" is left untouched.
+fn escape_namespaces(xml: &str) -> String {
+    let mut xml = xml.to_string();
+    for namespace in PRESERVED_NAMESPACES {
+        for tag_start in ["<", "</"] {
+            let from = format!("{}{}:", tag_start, namespace);
+            let to = format!("{}{}{}", tag_start, namespace, NAMESPACE_PLACEHOLDER);
+            xml = xml.replace(&from, &to);
+        }
+    }
+    xml
+}
+
+fn restore_key(key: &str) -> String {
+    let mut key = key.to_string();
+    for namespace in PRESERVED_NAMESPACES {
+        let from = format!("{}{}", namespace, NAMESPACE_PLACEHOLDER);
+        key = key.replace(&from, &format!("{}:", namespace));
+    }
+    key
+}
+
+/// Changes the escaped keys back to `namespace:key`, including those of nested elements.
+fn restore_namespaces(val: &Value) -> Value {
+    match val {
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(key, val)| (restore_key(key), restore_namespaces(val)))
+                .collect(),
+        ),
+        Value::Array(vals) => Value::Array(vals.iter().map(restore_namespaces).collect()),
+        val => val.clone(),
+    }
 }
 
 /// Converts the podcast's xml string to serde values of the channel and the episodes.
 ///
 /// The library will merge different namespaces together, which is why we manually change
-/// the itunes namespace, and then after converting it, we change it back. Preserving itunes:XXX as
-/// separate keys.
+/// the tags of the [`PRESERVED_NAMESPACES`], and then after converting it, we change them back.
+/// Preserving itunes:XXX, podcast:XXX, psc:XXX and sy:XXX as separate keys.
+///
+/// Atom feeds are mapped onto the same keys as RSS feeds, see [`atom`].
 fn xml_to_value(xml: &str, ui: &DownloadBar) -> Option<(RawPodcast, Vec<RawEpisode>)> {
     ui.log_info("converting xml to serde values");
-    let placeholder = "__placeholder__";
-    let replacement = format!("itunes{}", placeholder);
-    let xml = xml.replace("itunes:", &replacement);
-    let mut val = match get_inner_channel(xml) {
-        Some(val) => val,
+    let xml = escape_namespaces(xml);
+    let root = match get_feed_root(xml) {
+        Some(root) => root,
         None => {
-            ui.log_error("failed to find rss/channel xml tags");
+            ui.log_error("failed to find rss/channel or atom feed xml tags");
             return None;
         }
     };
 
+    let is_atom = matches!(root, FeedRoot::Atom(_));
+    if is_atom {
+        ui.log_debug("parsing atom feed");
+    }
+
+    let items_key = root.items_key();
+    let mut val = root.into_inner();
+
     // Create a new map to store the transformed keys at the top level
     let mut new_map: Map<String, Value> = Map::new();
 
     if let Some(obj) = val.as_object() {
         for (key, value) in obj {
-            let new_key = key.replace(&replacement, "itunes:");
-            new_map.insert(new_key, value.clone());
+            new_map.insert(restore_key(key), restore_namespaces(value));
         }
     }
 
+    if is_atom {
+        atom::map_feed(&mut new_map);
+    }
+
     let podcast = RawPodcast::new(new_map);
 
-    let items = std::mem::take(val.as_object_mut()?.get_mut("item")?.as_array_mut()?);
+    // A feed with a single episode is parsed as an object rather than an array.
+    let items = match std::mem::take(val.as_object_mut()?.get_mut(items_key)?) {
+        Value::Array(items) => items,
+        item @ Value::Object(_) => vec![item],
+        _ => return None,
+    };
 
     let episodes = items
         .iter()
         .map(|item| {
             let mut new_item_map: Map<String, Value> = Map::new();
             for (key, val) in item.as_object().expect("unexpected serde type").iter() {
-                let new_key = key.replace(&replacement, "itunes:");
-                new_item_map.insert(new_key, val.clone());
+                new_item_map.insert(restore_key(key), restore_namespaces(val));
+            }
+            if is_atom {
+                atom::map_entry(&mut new_item_map);
             }
             RawEpisode::new(new_item_map)
         })
@@ -72,6 +179,91 @@ fn xml_to_value(xml: &str, ui: &DownloadBar) -> Option<(RawPodcast, Vec<RawEpiso
     Some((podcast, episodes))
 }
 
+/// Parses the feed into the podcast and its episodes, ordered by publication date.
+fn parse_feed(
+    feed: &utils::FeedText,
+    ui: &DownloadBar,
+) -> Result<(RawPodcast, Vec<episode::Attributes>), String> {
+    let parsed = if feed.is_json() {
+        json_feed::parse(&feed.text, ui)
+    } else {
+        xml_to_value(&feed.text, ui)
+    };
+
+    let Some((raw_podcast, raw_episodes)) = parsed else {
+        return Err("failed to parse feed".into());
+    };
+
+    let mut attrs = vec![];
+
+    for episode in raw_episodes {
+        ui.log_trace("parsing attributes from raw episode");
+        match episode::Attributes::new(episode) {
+            Ok(attr) => attrs.push(attr),
+            Err(e) => {
+                ui.log_debug(e);
+            }
+        }
+    }
+
+    attrs.sort_by_key(|attr| attr.published());
+    Ok((raw_podcast, attrs))
+}
+
+/// What a feed holds, shown before its podcast is added.
+#[derive(Debug, Clone)]
+pub struct FeedSummary {
+    /// The title of the channel.
+    pub title: Option<String>,
+    pub episodes: usize,
+    /// The title and publish date of the latest episode.
+    pub latest: Option<(String, time::Duration)>,
+}
+
+impl FeedSummary {
+    pub fn print(&self) {
+        eprintln!("title: {}", self.title.as_deref().unwrap_or("-"));
+        eprintln!("episodes: {}", self.episodes);
+
+        if let Some((title, published)) = &self.latest {
+            let published = chrono::DateTime::from_timestamp(published.as_secs() as i64, 0)
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            eprintln!("latest episode: {} {}", published, title);
+        }
+    }
+}
+
+/// Fetches and parses a feed without adding its podcast, so that a broken url is caught
+/// right away instead of at the next sync.
+pub async fn fetch_feed(
+    client: &reqwest::Client,
+    url: &str,
+    global_config: &GlobalConfig,
+    ui: &DownloadBar,
+) -> Result<FeedSummary, String> {
+    let feed = utils::download_text(client, url, false, global_config.feed_retry(), ui)
+        .await
+        .ok_or("failed to download feed")?;
+
+    let (raw_podcast, episodes) = parse_feed(&feed, ui)?;
+
+    let title = raw_podcast
+        .get_text("title")
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty());
+
+    let latest = episodes
+        .last()
+        .map(|episode| (episode.title().to_string(), episode.published()));
+
+    Ok(FeedSummary {
+        title,
+        episodes: episodes.len(),
+        latest,
+    })
+}
+
 #[derive(Debug)]
 pub struct RawPodcast(Map<String, serde_json::Value>);
 
@@ -84,6 +276,10 @@ impl RawPodcast {
         utils::val_to_str(self.0.get(key)?)
     }
 
+    pub fn get_text(&self, key: &str) -> Option<String> {
+        utils::val_to_string(self.0.get(key)?)
+    }
+
     pub fn title(&self) -> &str {
         self.get_str("title").unwrap()
     }
@@ -114,49 +310,139 @@ impl RawPodcast {
         let inner = self.0.get("image")?;
         utils::val_to_url(inner)
     }
+
+    /// The channel-wide `podcast:value`, which episodes may override.
+    pub fn value(&self) -> Option<podcast_ns::ValueBlock> {
+        podcast_ns::value(&self.0)
+    }
+
+    /// Where the feed has moved to, as announced by `<itunes:new-feed-url>`.
+    pub fn new_feed_url(&self) -> Option<String> {
+        self.get_text("itunes:new-feed-url")
+            .map(|url| url.trim().to_string())
+            .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+    }
+
+    /// How long the feed asks to be cached, from `<ttl>` or the syndication module
+    /// (`sy:updatePeriod` and `sy:updateFrequency`), whichever is longer.
+    pub fn refresh_hint(&self) -> Option<time::Duration> {
+        let ttl = self
+            .get_text("ttl")
+            .and_then(|ttl| ttl.trim().parse::<u64>().ok())
+            .map(|minutes| time::Duration::from_secs(minutes * 60));
+
+        let period = self
+            .get_text("sy:updatePeriod")
+            .and_then(|period| match period.trim() {
+                "hourly" => Some(3600),
+                "daily" => Some(86400),
+                "weekly" => Some(604800),
+                "monthly" => Some(2592000),
+                "yearly" => Some(31536000),
+                _ => None,
+            });
+
+        let frequency = self
+            .get_text("sy:updateFrequency")
+            .and_then(|frequency| frequency.trim().parse::<u64>().ok())
+            .filter(|frequency| *frequency > 0)
+            .unwrap_or(1);
+
+        let update_period = period.map(|secs| time::Duration::from_secs(secs / frequency));
+
+        ttl.max(update_period).filter(|hint| !hint.is_zero())
+    }
 }
 
+const IDS_CHANGED: &str = "the tracker doesn't match any downloaded episode, if id_pattern was changed, migrate it with --migrate-ids";
+
 #[derive(Debug)]
 pub struct Podcast {
+    name: String,
     episodes: Vec<Episode>,
     client: Arc<reqwest::Client>,
     mode: DownloadMode,
+    /// The download trackers of the episodes, loaded once per tracker path.
+    trackers: HashMap<PathBuf, DownloadedEpisodes>,
+    /// How many episodes may be downloaded at the same time.
+    max_downloads: usize,
+    rate_limiter: RateLimiter,
+    retention: Retention,
+    /// How long the feed asks to be cached, see [`RawPodcast::refresh_hint`].
+    refresh_hint: Option<time::Duration>,
+    /// Kept to evaluate other patterns than the configured ones, see [`Podcast::migrate_ids`].
+    raw_podcast: RawPodcast,
+    /// Kept to name the files of episodes that have left the feed, see [`Podcast::tracked_file_stem`].
+    name_pattern: FullPattern,
+    /// Where the feed has moved to, through permanent redirects or `<itunes:new-feed-url>`.
+    moved_to: Option<String>,
 }
 
 impl Podcast {
+    /// Fetches and parses the feed of the podcast.
+    ///
+    /// The feed cache is left untouched unless `use_cache` is set.
     pub async fn new(
         name: String,
         config: PodcastConfig,
         global_config: &GlobalConfig,
         client: Arc<reqwest::Client>,
+        use_cache: bool,
         ui: &DownloadBar,
     ) -> Result<Podcast, String> {
         ui.fetching();
         ui.log_info("downloading podcast info...");
-        let Some(xml_string) = utils::download_text(&client, &config.url, ui).await else {
-            return Err("failed to download xml-file".into());
-        };
-
-        let Some((raw_podcast, raw_episodes)) = xml_to_value(&xml_string, ui) else {
-            return Err("failed to parse xml".into());
+        let Some(feed) = utils::download_text(
+            &client,
+            &config.url,
+            use_cache,
+            global_config.feed_retry(),
+            ui,
+        )
+        .await
+        else {
+            return Err("failed to download feed".into());
         };
 
-        let episode_attrs = {
-            let mut attrs = vec![];
+        Self::from_feed(name, config, global_config, client, feed, ui).await
+    }
 
-            for episode in raw_episodes {
-                ui.log_trace("parsing attributes from raw episode");
-                match episode::Attributes::new(episode) {
-                    Ok(attr) => attrs.push(attr),
-                    Err(e) => {
-                        ui.log_debug(e);
-                    }
+    /// Like [`Podcast::new`], but falls back to the cached feed when it can't be downloaded.
+    pub async fn new_or_cached(
+        name: String,
+        config: PodcastConfig,
+        global_config: &GlobalConfig,
+        client: Arc<reqwest::Client>,
+        ui: &DownloadBar,
+    ) -> Result<Podcast, String> {
+        let feed =
+            match utils::download_text(&client, &config.url, true, global_config.feed_retry(), ui)
+                .await
+            {
+                Some(feed) => feed,
+                None => {
+                    eprintln!("warning: failed to download feed, using cached copy");
+                    cache::FeedCache::feed(&config.url).ok_or("failed to download feed")?
                 }
-            }
+            };
 
-            attrs.sort_by_key(|attr| attr.published());
-            attrs
-        };
+        Self::from_feed(name, config, global_config, client, feed, ui).await
+    }
+
+    async fn from_feed(
+        name: String,
+        config: PodcastConfig,
+        global_config: &GlobalConfig,
+        client: Arc<reqwest::Client>,
+        feed: utils::FeedText,
+        ui: &DownloadBar,
+    ) -> Result<Podcast, String> {
+        let (raw_podcast, episode_attrs) = parse_feed(&feed, ui)?;
+
+        let moved_to = feed
+            .moved_to
+            .or_else(|| raw_podcast.new_feed_url())
+            .filter(|url| url != &config.url);
 
         let mut episodes = vec![];
         for (index, attr) in episode_attrs.into_iter().enumerate() {
@@ -177,33 +463,748 @@ impl Podcast {
         }
 
         let mode = DownloadMode::new(global_config, &config);
+        let max_downloads = config.max_downloads(global_config);
+        let rate_limiter = config.rate_limiter(global_config);
+        let retention = config.retention(global_config);
+        let name_pattern = config.name_pattern(global_config);
+        let refresh_hint = raw_podcast.refresh_hint();
+
+        let mut trackers = HashMap::new();
+        for episode in &episodes {
+            let path = episode.tracker_path();
+            if !trackers.contains_key(path) {
+                trackers.insert(path.to_path_buf(), DownloadedEpisodes::load(path));
+            }
+        }
 
         Ok(Podcast {
+            name,
             episodes,
             client,
             mode,
+            trackers,
+            max_downloads,
+            rate_limiter,
+            retention,
+            refresh_hint,
+            raw_podcast,
+            name_pattern,
+            moved_to,
         })
     }
 
-    pub async fn sync(self, ui: &mut DownloadBar) -> Vec<PathBuf> {
+    pub fn refresh_hint(&self) -> Option<time::Duration> {
+        self.refresh_hint
+    }
+
+    pub fn moved_to(&self) -> Option<&str> {
+        self.moved_to.as_deref()
+    }
+
+    /// Points the podcast at the new url of its feed in `podcasts.toml`, if the feed has moved.
+    ///
+    /// Without `update` the move is only reported. Returns the new url when it was stored.
+    pub fn follow_move(&self, update: bool, ui: &DownloadBar) -> Option<String> {
+        let url = self.moved_to()?;
+
+        if !update {
+            ui.log_warn(format!("feed has moved to {}", url));
+            ui.eprintln(&format!(
+                "{}: feed has moved to {}, run with --update-urls to follow it",
+                &self.name, url
+            ));
+            return None;
+        }
+
+        match PodcastConfigs::update_url(&self.name, url) {
+            Ok(old_url) => {
+                ui.log_info(format!("feed url changed from {} to {}", old_url, url));
+                ui.eprintln(&format!(
+                    "{}: feed has moved, url changed from {} to {}",
+                    &self.name, old_url, url
+                ));
+                Some(url.to_string())
+            }
+            Err(e) => {
+                ui.log_error(&e);
+                ui.eprintln(&format!("{}: {}", &self.name, e));
+                None
+            }
+        }
+    }
+
+    /// Converts the trackers of the podcast to the JSON format, returning how many entries
+    /// were converted.
+    ///
+    /// The episodes are looked up in the feed to record their url, publish date and file.
+    pub fn migrate_trackers(&self) -> Result<usize, String> {
+        let mut converted = 0;
+
+        for path in self.trackers.keys().filter(|path| path.exists()) {
+            converted += DownloadedEpisodes::migrate(path, |entry| {
+                // Could be an entry of another podcast sharing the tracker.
+                let Some(episode) = self.episodes.iter().find(|ep| ep.get_id() == entry.id) else {
+                    return entry;
+                };
+
+                let extension = utils::guess_extension(episode);
+                let file = episode.file_path(extension.as_deref().map(OsStr::new));
+
+                TrackerEntry {
+                    podcast: Some(self.name.clone()),
+                    url: Some(episode.attrs.url().to_string()),
+                    published: Some(episode.attrs.published().as_secs()),
+                    ..entry
+                }
+                .with_file(&file)
+            })?;
+        }
+
+        Ok(converted)
+    }
+
+    /// Rewrites the ids in the trackers from an old `id_pattern` to the configured one,
+    /// returning how many entries were changed.
+    ///
+    /// Both patterns are evaluated for every episode in the feed, so entries of episodes that
+    /// are no longer in the feed stay as they are. With `dry_run` set, the changes are only printed.
+    pub fn migrate_ids(
+        &self,
+        old_pattern: &str,
+        dry_run: bool,
+        ui: &DownloadBar,
+    ) -> Result<usize, String> {
+        let old_pattern = FullPattern::from_str(old_pattern);
+        let mut renames: HashMap<&Path, HashMap<String, String>> = HashMap::new();
+        let mut ambiguous = HashSet::new();
+
+        for episode in &self.episodes {
+            let data = EvalData::new(&self.name, &self.raw_podcast, &episode.attrs);
+            let old_id = old_pattern.evaluate(data).replace(" ", "_");
+            let new_id = episode.get_id();
+            let tracker = self.tracker(episode);
+
+            if old_id == new_id || !tracker.contains_episode(&old_id) {
+                continue;
+            }
+
+            if tracker.contains_episode(&new_id) {
+                episode.log_debug(ui, "skipping episode: already tracked with the new id");
+                continue;
+            }
+
+            let renames = renames.entry(episode.tracker_path()).or_default();
+
+            // The old id can't be migrated if it was shared by several episodes.
+            match renames.get(&old_id) {
+                Some(id) if id != &new_id => {
+                    ambiguous.insert(old_id);
+                }
+                _ => {
+                    renames.insert(old_id, new_id);
+                }
+            }
+        }
+
+        for old_id in &ambiguous {
+            eprintln!(
+                "{}: skipping '{}': it matches several episodes",
+                &self.name, old_id
+            );
+            for renames in renames.values_mut() {
+                renames.remove(old_id);
+            }
+        }
+
+        let mut renamed = 0;
+
+        for (path, renames) in &renames {
+            if dry_run {
+                let mut renames: Vec<_> = renames.iter().collect();
+                renames.sort();
+                for (old_id, new_id) in renames {
+                    println!("{}: {} -> {}", &self.name, old_id, new_id);
+                }
+            } else {
+                renamed += DownloadedEpisodes::rename_ids(path, renames)?;
+            }
+        }
+
+        match dry_run {
+            true => Ok(renames.values().map(HashMap::len).sum()),
+            false => Ok(renamed),
+        }
+    }
+
+    /// The paths of the podcast that change when it's named `name` instead.
+    pub fn relocations(
+        &self,
+        name: &str,
+        config: &PodcastConfig,
+        global_config: &GlobalConfig,
+    ) -> Vec<Relocation> {
+        let mut relocations: Vec<Relocation> = vec![];
+
+        for episode in &self.episodes {
+            let data = EvalData::new(name, &self.raw_podcast, &episode.attrs);
+            let renamed = Config::new(global_config, config, data);
+            let current = &episode.config;
+
+            let paths = [
+                (
+                    PathKind::Download,
+                    Some(&current.download_path),
+                    Some(&renamed.download_path),
+                ),
+                (
+                    PathKind::Partial,
+                    current.partial_path.as_ref(),
+                    renamed.partial_path.as_ref(),
+                ),
+                (
+                    PathKind::Tracker,
+                    Some(&current.tracker_path),
+                    Some(&renamed.tracker_path),
+                ),
+                (
+                    PathKind::Symlink,
+                    current.symlink.as_ref(),
+                    renamed.symlink.as_ref(),
+                ),
+            ];
+
+            for (kind, from, to) in paths {
+                let (Some(from), Some(to)) = (from, to) else {
+                    continue;
+                };
+
+                let relocation = Relocation {
+                    kind,
+                    from: from.clone(),
+                    to: to.clone(),
+                };
+
+                if from != to && !relocations.contains(&relocation) {
+                    relocations.push(relocation);
+                }
+            }
+        }
+
+        relocations.sort();
+        relocations
+    }
+
+    /// Renames the tracker entries of the podcast along with the podcast itself.
+    ///
+    /// With `move_files` set, the paths that follow from the name of the podcast are moved
+    /// to where the new name puts them, and the symlinks into them are updated. Without it,
+    /// the podcast can't be renamed while any of those paths exist.
+    pub fn rename(
+        &self,
+        new_name: &str,
+        config: &PodcastConfig,
+        global_config: &GlobalConfig,
+        move_files: bool,
+    ) -> Result<(), String> {
+        let moved = self.relocations(new_name, config, global_config);
+
+        // Otherwise the podcast would start over in new paths, downloading everything again.
+        if !move_files {
+            let existing: Vec<String> = moved
+                .iter()
+                .filter(|relocation| relocation.from.exists())
+                .map(|relocation| {
+                    format!(
+                        "\n  {} -> {}",
+                        relocation.from.display(),
+                        relocation.to.display()
+                    )
+                })
+                .collect();
+
+            if !existing.is_empty() {
+                return Err(format!(
+                    "these paths follow from the podcast name, use --move-files to move them along:{}",
+                    existing.concat()
+                ));
+            }
+        }
+
+        let moved = match move_files {
+            true => moved,
+            false => vec![],
+        };
+
+        let applied = relocate::apply_all(&moved)?;
+        let downloads: Vec<&Relocation> = moved
+            .iter()
+            .filter(|r| r.kind == PathKind::Download)
+            .collect();
+        let symlink_dirs: Vec<PathBuf> = self
+            .symlink_dirs()
+            .map(|dir| moved_path(&moved, dir))
+            .collect();
+
+        let relink = |forward: bool| -> Result<(), String> {
+            for dir in &symlink_dirs {
+                for download in &downloads {
+                    match forward {
+                        true => relocate::relink(dir, &download.from, &download.to)?,
+                        false => relocate::relink(dir, &download.to, &download.from)?,
+                    };
+                }
+            }
+            Ok(())
+        };
+
+        if let Err(e) = relink(true) {
+            let _ = relink(false);
+            relocate::undo_all(&applied)?;
+            return Err(e);
+        }
+
+        for relocation in &applied {
+            eprintln!(
+                "moved {} to {}",
+                relocation.from.display(),
+                relocation.to.display()
+            );
+        }
+
+        for path in self.trackers.keys() {
+            let path = moved_path(&moved, path);
+            if !path.exists() {
+                continue;
+            }
+
+            DownloadedEpisodes::update(&path, |entry| {
+                if entry.podcast.as_ref() != Some(&self.name) {
+                    return Some(entry);
+                }
+
+                Some(TrackerEntry {
+                    podcast: Some(new_name.to_string()),
+                    path: entry.path.as_ref().map(|path| moved_path(&moved, path)),
+                    ..entry
+                })
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the paths that follow from the name of the podcast, along with its symlinks
+    /// and tracker entries in the paths it may share with other podcasts.
+    pub fn delete_files(
+        &self,
+        config: &PodcastConfig,
+        global_config: &GlobalConfig,
+    ) -> Result<(), String> {
+        // Any other name shows which paths follow from the name.
+        let other_name = format!("_{}", &self.name);
+        let relocations = self.relocations(&other_name, config, global_config);
+        let is_deleted = |path: &Path| relocations.iter().any(|r| path.starts_with(&r.from));
+
+        for dir in self.symlink_dirs().filter(|dir| !is_deleted(dir)) {
+            for download in relocations.iter().filter(|r| r.kind == PathKind::Download) {
+                relocate::unlink(dir, &download.from)?;
+            }
+        }
+
+        for path in self.trackers.keys().filter(|path| !is_deleted(path)) {
+            if path.exists() {
+                DownloadedEpisodes::update(path, |entry| {
+                    match entry.podcast.as_ref() == Some(&self.name) {
+                        true => None,
+                        false => Some(entry),
+                    }
+                })?;
+            }
+        }
+
+        for relocation in &relocations {
+            if relocation.delete()? {
+                eprintln!("deleted {}", relocation.from.display());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn symlink_dirs(&self) -> impl Iterator<Item = &PathBuf> {
+        let mut dirs: Vec<&PathBuf> = self
+            .episodes
+            .iter()
+            .filter_map(|episode| episode.config.symlink.as_ref())
+            .collect();
+
+        dirs.sort();
+        dirs.dedup();
+        dirs.into_iter()
+    }
+
+    /// Whether the `id_pattern` seems to have changed since episodes were downloaded.
+    ///
+    /// That's the case when none of the episodes the index knows as downloaded are found
+    /// in their tracker, even though it isn't empty.
+    fn ids_changed(&self) -> bool {
+        let Ok(index) = EpisodeIndex::load(&self.name) else {
+            return false;
+        };
+
+        let downloaded: Vec<&Episode> = self
+            .episodes
+            .iter()
+            .filter(|episode| {
+                matches!(
+                    index.status(episode.attrs.guid()),
+                    Some(EpisodeStatus::Downloaded | EpisodeStatus::Deleted)
+                )
+            })
+            .collect();
+
+        !downloaded.is_empty()
+            && downloaded.iter().all(|episode| {
+                let tracker = self.tracker(episode);
+                !tracker.is_empty() && !episode.is_downloaded(tracker)
+            })
+    }
+
+    pub async fn sync(self, limits: &TransferLimits, ui: &mut DownloadBar) -> Vec<PathBuf> {
         ui.init();
         ui.log_info("syncing...");
 
+        // Otherwise every episode in the download window would be downloaded again.
+        if self.ids_changed() {
+            ui.error(IDS_CHANGED);
+            return vec![];
+        }
+
         let episodes = self.pending_episodes();
-        let mut downloaded = vec![];
+        if episodes.is_empty() {
+            ui.log_info("no pending episodes");
+        }
+
+        let paths = self.download_episodes(episodes, limits, ui).await;
+        self.apply_retention(ui);
+        paths
+    }
 
-        for (index, episode) in episodes.iter().enumerate() {
-            ui.begin_download(&episode, index, episodes.len());
+    /// Deletes the downloaded episodes that fall outside the retention settings.
+    ///
+    /// They stay in the tracker, but are marked as deleted in the index.
+    fn apply_retention(&self, ui: &DownloadBar) {
+        if !self.retention.is_enabled() {
+            return;
+        }
+
+        let expired = self.retention.expired(self.stored_episodes(ui));
+        if expired.is_empty() {
+            return;
+        }
+
+        let mut index = EpisodeIndex::load(&self.name).unwrap_or_else(|e| {
+            ui.log_warn(e);
+            EpisodeIndex::default()
+        });
+
+        for episode in &expired {
+            match episode.delete() {
+                Ok(()) => {
+                    ui.log_info(format!("deleted {}", episode.title));
+                    if let Some(guid) = &episode.guid {
+                        index.set_status(guid, EpisodeStatus::Deleted);
+                    }
+                }
+                Err(e) => ui.log_error(e),
+            }
+        }
+
+        self.save_index(&mut index, ui);
+    }
+
+    /// The name the `name_pattern` gave the file of an episode that has left the feed.
+    ///
+    /// The episode is pieced together from what the tracker recorded, so patterns that need
+    /// more than that evaluate to a name no file has.
+    fn tracked_file_stem(&self, entry: &TrackerEntry) -> Option<String> {
+        let published = chrono::DateTime::from_timestamp(entry.published? as i64, 0)?;
+
+        let mut enclosure = Map::new();
+        enclosure.insert("@url".to_string(), Value::from(entry.url.clone()?));
+
+        let mut raw = Map::new();
+        raw.insert("title".to_string(), Value::from(entry.title.clone()));
+        raw.insert("guid".to_string(), Value::from(entry.id.clone()));
+        raw.insert("pubDate".to_string(), Value::from(published.to_rfc2822()));
+        raw.insert("enclosure".to_string(), Value::Object(enclosure));
+
+        let attrs = episode::Attributes::new(RawEpisode::new(raw)).ok()?;
+        let data = EvalData::new(&self.name, &self.raw_podcast, &attrs);
+        Some(sanitize_filename::sanitize(
+            self.name_pattern.evaluate(data),
+        ))
+    }
+
+    /// The downloaded episodes of the podcast whose files are still there, going by the trackers.
+    fn stored_episodes(&self, ui: &DownloadBar) -> Vec<StoredEpisode> {
+        let mut stored: Vec<StoredEpisode> = vec![];
+
+        let in_feed: HashSet<OsString> = self
+            .episodes
+            .iter()
+            .filter_map(|ep| ep.file_path(None).file_name().map(OsStr::to_os_string))
+            .collect();
+
+        let mut dirs: Vec<&Path> = self
+            .episodes
+            .iter()
+            .map(|ep| ep.config.download_path.as_path())
+            .collect();
+        dirs.sort();
+        dirs.dedup();
 
-            match episode.download(&self.client, ui).await {
-                Ok(downloaded_episode) => downloaded.push(downloaded_episode),
+        for path in self.trackers.keys() {
+            let entries = match DownloadedEpisodes::entries(path) {
+                Ok(entries) => entries,
                 Err(e) => {
-                    ui.error(&e);
-                    break;
+                    ui.log_warn(e);
+                    continue;
                 }
             };
+
+            for entry in entries {
+                // Shared trackers hold the episodes of other podcasts as well.
+                if entry
+                    .podcast
+                    .as_ref()
+                    .is_some_and(|name| name != &self.name)
+                {
+                    continue;
+                }
+
+                let episode = self.episodes.iter().find(|ep| ep.get_id() == entry.id);
+
+                // The text format doesn't record the path, so it's found through the feed,
+                // or by the name it was given once the episode has left the feed.
+                let path = match (&entry.path, episode) {
+                    (Some(path), _) => Some(path.clone()),
+                    (None, Some(episode)) => find_file(episode, &entry.sidecars),
+                    // Might as well belong to another podcast sharing the tracker.
+                    (None, None) if entry.podcast.is_none() => {
+                        ui.log_debug(format!(
+                            "skipping {}: the tracker doesn't record its podcast or path",
+                            entry.title
+                        ));
+                        continue;
+                    }
+                    (None, None) => self
+                        .tracked_file_stem(&entry)
+                        .and_then(|stem| find_file_by_stem(&dirs, &stem, &in_feed)),
+                };
+
+                let Some(path) = path else {
+                    continue;
+                };
+
+                let Ok(metadata) = std::fs::metadata(&path) else {
+                    continue;
+                };
+
+                if !metadata.is_file() || stored.iter().any(|ep| ep.path == path) {
+                    continue;
+                }
+
+                let symlink = episode
+                    .or(self.episodes.first())
+                    .and_then(|ep| ep.config.symlink.as_ref())
+                    .zip(path.file_name())
+                    .map(|(dir, name)| dir.join(name));
+
+                stored.push(StoredEpisode {
+                    title: entry.title,
+                    guid: episode.map(|ep| ep.attrs.guid().to_string()),
+                    downloaded: entry.downloaded,
+                    size: metadata.len(),
+                    sidecars: match entry.path {
+                        Some(_) => entry
+                            .sidecars
+                            .iter()
+                            .map(|name| path.with_file_name(name))
+                            .collect(),
+                        None => find_sidecars(&path),
+                    },
+                    symlink,
+                    path,
+                });
+            }
+        }
+
+        stored
+    }
+
+    /// Downloads the selected episodes, regardless of the download mode.
+    ///
+    /// Episodes that have already been downloaded are skipped.
+    pub async fn download_selected(
+        self,
+        selector: &EpisodeSelector,
+        limits: &TransferLimits,
+        ui: &mut DownloadBar,
+    ) -> Vec<PathBuf> {
+        ui.init();
+        ui.log_info("downloading selected episodes...");
+
+        let selected = match self.select(selector) {
+            Ok(selected) => selected,
+            Err(e) => {
+                ui.error(&e);
+                return vec![];
+            }
+        };
+
+        let episodes = selected
+            .into_iter()
+            .filter(|episode| {
+                let downloaded = episode.is_downloaded(self.tracker(episode));
+                if downloaded {
+                    episode.log_debug(ui, "skipping episode: already downloaded");
+                }
+                !downloaded
+            })
+            .collect();
+
+        self.download_episodes(episodes, limits, ui).await
+    }
+
+    /// Marks the selected episodes as downloaded without downloading them, returning how
+    /// many were marked.
+    pub fn mark_selected(
+        &self,
+        selector: &EpisodeSelector,
+        ui: &DownloadBar,
+    ) -> Result<usize, String> {
+        let mut index = self.index(ui);
+        let mut marked = HashSet::new();
+
+        for episode in self.select(selector)? {
+            // Episodes may share an id, which should only be tracked once.
+            let id = (episode.tracker_path(), episode.get_id());
+            if episode.is_downloaded(self.tracker(episode)) || !marked.insert(id) {
+                episode.log_debug(ui, "skipping episode: already downloaded");
+                continue;
+            }
+
+            DownloadedEpisodes::mark(episode.tracker_path(), episode)?;
+            index.set_status(episode.attrs.guid(), EpisodeStatus::Downloaded);
+            episode.log_debug(ui, "marked as downloaded");
+        }
+
+        self.save_index(&mut index, ui);
+        Ok(marked.len())
+    }
+
+    /// Removes the selected episodes from the trackers, so that they're downloaded again,
+    /// returning how many were removed. The downloaded files are left alone.
+    pub fn unmark_selected(
+        &self,
+        selector: &EpisodeSelector,
+        ui: &DownloadBar,
+    ) -> Result<usize, String> {
+        let mut index = self.index(ui);
+        let mut ids: HashMap<&Path, HashSet<String>> = HashMap::new();
+
+        for episode in self.select(selector)? {
+            if !episode.is_downloaded(self.tracker(episode)) {
+                episode.log_debug(ui, "skipping episode: not downloaded");
+                continue;
+            }
+
+            ids.entry(episode.tracker_path())
+                .or_default()
+                .insert(episode.get_id());
+            index.set_status(episode.attrs.guid(), EpisodeStatus::New);
         }
 
+        let mut removed = 0;
+        for (path, ids) in &ids {
+            removed += DownloadedEpisodes::unmark(path, ids)?;
+        }
+
+        self.save_index(&mut index, ui);
+        Ok(removed)
+    }
+
+    fn select(&self, selector: &EpisodeSelector) -> Result<Vec<&Episode>, String> {
+        let selected: Vec<&Episode> = self
+            .episodes
+            .iter()
+            .filter(|episode| selector.matches(episode))
+            .collect();
+
+        if selected.is_empty() {
+            return Err("no episodes matched the selection".to_string());
+        }
+
+        Ok(selected)
+    }
+
+    async fn download_episodes(
+        &self,
+        episodes: Vec<&Episode>,
+        limits: &TransferLimits,
+        ui: &mut DownloadBar,
+    ) -> Vec<PathBuf> {
+        let mut index = self.index(ui);
+
+        if episodes.is_empty() {
+            self.save_index(&mut index, ui);
+            ui.complete();
+            return vec![];
+        }
+
+        let qty = episodes.len();
+        let shared_ui: &DownloadBar = ui;
+
+        // A failed episode doesn't stop the others, it's retried on the next sync.
+        let results: Vec<_> = stream::iter(0..qty)
+            .map(|i| {
+                let episode = episodes[i];
+                async move {
+                    shared_ui.begin_download(episode, i, qty);
+                    let rate_limiters = [limits.rate(), &self.rate_limiter];
+                    let result = episode
+                        .download(&self.client, limits, &rate_limiters, shared_ui)
+                        .await;
+                    (episode, result)
+                }
+            })
+            .buffer_unordered(self.max_downloads)
+            .collect()
+            .await;
+
+        let mut downloaded = vec![];
+        let mut errors = vec![];
+
+        for (episode, result) in results {
+            match result {
+                Ok(downloaded_episode) => {
+                    index.set_status(episode.attrs.guid(), EpisodeStatus::Downloaded);
+                    downloaded.push(downloaded_episode);
+                }
+                Err(e) => {
+                    ui.log_error(format!(
+                        "failed to download {}: {}",
+                        episode.attrs.title(),
+                        e
+                    ));
+                    errors.push(e);
+                }
+            }
+        }
+
+        self.save_index(&mut index, ui);
+
         let mut paths = vec![];
 
         ui.hook_status();
@@ -212,17 +1213,174 @@ impl Podcast {
             paths.push(episode.into_path());
         }
 
+        match errors.first() {
+            Some(e) if errors.len() == 1 => ui.error(e),
+            Some(e) => ui.error(&format!(
+                "{} of {} episodes failed: {}",
+                errors.len(),
+                qty,
+                e
+            )),
+            None => ui.complete(),
+        }
+
+        paths
+    }
+
+    /// Prints the episodes a sync would download, along with where they'd end up.
+    ///
+    /// Nothing is downloaded or written, the planned paths are returned.
+    pub fn print_plan(&self, ui: &mut DownloadBar) -> Vec<PathBuf> {
+        let mut plan = String::new();
+        let mut paths = vec![];
+
+        if self.ids_changed() {
+            ui.error(IDS_CHANGED);
+            return paths;
+        }
+
+        for episode in self.pending_episodes() {
+            let extension = utils::guess_extension(episode);
+            let path = episode.file_path(extension.as_deref().map(OsStr::new));
+
+            plan.push_str(&format!("{}: {}\n", &self.name, episode.attrs.title()));
+            plan.push_str(&format!("    path: {}", path.display()));
+
+            if let Some(symlink) = &episode.config.symlink {
+                let symlink = symlink.join(path.file_name().unwrap());
+                plan.push_str(&format!("\n    symlink: {}", symlink.display()));
+            }
+
+            plan.push('\n');
+            paths.push(path);
+        }
+
+        if !plan.is_empty() {
+            ui.println(plan.trim_end());
+        }
+
         ui.complete();
         paths
     }
 
+    /// Prints every episode in the feed, whether it has been downloaded,
+    /// and whether the download mode would accept it.
+    pub fn print_episodes(&self) {
+        let qty = self.episodes.len();
+
+        println!("INDEX  PUBLISHED         SIZE  STATUS      VERDICT                  TITLE");
+
+        for episode in &self.episodes {
+            let published =
+                chrono::DateTime::from_timestamp(episode.attrs.published().as_secs() as i64, 0)
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default();
+
+            let size = episode
+                .attrs
+                .length()
+                .map(|size| indicatif::HumanBytes(size).to_string())
+                .unwrap_or_else(|| "-".to_string());
+
+            let status = if episode.is_downloaded(self.tracker(episode)) {
+                "downloaded"
+            } else {
+                "new"
+            };
+
+            println!(
+                "{:>5}  {:<10}  {:>10}  {:<10}  {:<23}  {}",
+                episode.index,
+                published,
+                size,
+                status,
+                episode.verdict(&self.mode, qty).to_string(),
+                episode.attrs.title()
+            );
+        }
+    }
+
+    /// Prints the entries of the trackers of the podcast, in the order they were added.
+    ///
+    /// Entries that a shared tracker recorded for other podcasts are left out.
+    pub fn print_tracker(&self) -> Result<(), String> {
+        let mut paths: Vec<&PathBuf> = self.trackers.keys().collect();
+        paths.sort();
+
+        for path in paths {
+            let entries: Vec<TrackerEntry> = DownloadedEpisodes::entries(path)?
+                .into_iter()
+                .filter(|entry| entry.podcast.as_ref().is_none_or(|name| name == &self.name))
+                .collect();
+
+            if self.trackers.len() > 1 {
+                println!("{}:", path.display());
+            }
+
+            let id_width = entries.iter().map(|entry| entry.id.chars().count()).max();
+            let id_width = id_width.unwrap_or_default().max(2);
+
+            println!("DOWNLOADED        {:<id_width$}  TITLE", "ID");
+
+            for entry in entries {
+                let downloaded = chrono::DateTime::from_timestamp(entry.downloaded as i64, 0)
+                    .map(|date| {
+                        date.with_timezone(&chrono::Local)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+
+                println!(
+                    "{:<16}  {:<id_width$}  {}",
+                    downloaded, entry.id, entry.title
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn tracker(&self, episode: &Episode) -> &DownloadedEpisodes {
+        &self.trackers[episode.tracker_path()]
+    }
+
+    /// The stored index of the podcast, updated with the episodes currently in the feed.
+    fn index(&self, ui: &DownloadBar) -> EpisodeIndex {
+        let mut index = EpisodeIndex::load(&self.name).unwrap_or_else(|e| {
+            ui.log_warn(e);
+            EpisodeIndex::default()
+        });
+
+        for episode in &self.episodes {
+            let status = if !episode.is_downloaded(self.tracker(episode)) {
+                EpisodeStatus::New
+            } else if index.status(episode.attrs.guid()) == Some(EpisodeStatus::Deleted) {
+                EpisodeStatus::Deleted
+            } else {
+                EpisodeStatus::Downloaded
+            };
+
+            index.upsert(IndexEntry::new(&episode.attrs, status));
+        }
+
+        index
+    }
+
+    fn save_index(&self, index: &mut EpisodeIndex, ui: &DownloadBar) {
+        ui.log_trace("saving episode index");
+        if let Err(e) = index.save(&self.name) {
+            ui.log_error(e);
+        }
+    }
+
     fn pending_episodes(&self) -> Vec<&Episode> {
         let qty = self.episodes.len();
 
         let mut pending: Vec<&Episode> = self
             .episodes
             .iter()
-            .filter(|episode| episode.should_download(&self.mode, qty))
+            .filter(|episode| episode.should_download(&self.mode, qty, self.tracker(episode)))
             .collect();
 
         // In backlog mode it makes more sense to download earliest episode first.
@@ -240,3 +1398,96 @@ impl Podcast {
         pending
     }
 }
+
+/// Finds the downloaded file of an episode, when the tracker didn't record its path.
+///
+/// The extension isn't known up front, so any file named after the episode will do,
+/// apart from its sidecar files.
+fn find_file(episode: &Episode, sidecars: &[String]) -> Option<PathBuf> {
+    let extension = utils::guess_extension(episode);
+    let guessed = episode.file_path(extension.as_deref().map(OsStr::new));
+    if guessed.is_file() {
+        return Some(guessed);
+    }
+
+    let stem = episode.file_path(None);
+    let stem = stem.file_name()?;
+
+    std::fs::read_dir(&episode.config.download_path)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            path.file_stem() == Some(stem)
+                && path.extension().is_some_and(|ext| ext != "partial")
+                && !sidecars.iter().any(|sidecar| sidecar.as_str() == name)
+                && path.is_file()
+        })
+}
+
+/// Extensions of the transcripts and chapters downloaded next to episodes.
+const SIDECAR_EXTENSIONS: [&str; 6] = ["srt", "vtt", "json", "html", "txt", "chapters.json"];
+
+/// Finds the file of an episode that has left the feed by its name without the extension.
+///
+/// Files of episodes still in the feed don't count, and a name found in several
+/// directories can't tell them apart.
+fn find_file_by_stem(
+    dirs: &[&Path],
+    name_stem: &str,
+    in_feed: &HashSet<OsString>,
+) -> Option<PathBuf> {
+    if name_stem.trim().is_empty() {
+        return None;
+    }
+
+    let mut found = dirs
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let (Some(name), Some(stem), Some(ext)) =
+                (path.file_name(), path.file_stem(), path.extension())
+            else {
+                return false;
+            };
+
+            !name.to_string_lossy().starts_with('.')
+                && ext != "partial"
+                && !SIDECAR_EXTENSIONS.iter().any(|sidecar| ext == *sidecar)
+                && !in_feed.contains(stem)
+                && stem == name_stem
+                && path.is_file()
+        });
+
+    let path = found.next()?;
+    found.next().is_none().then_some(path)
+}
+
+/// The sidecar files next to an episode, for trackers that don't record them.
+fn find_sidecars(path: &Path) -> Vec<PathBuf> {
+    let Some(stem) = path.file_stem() else {
+        return vec![];
+    };
+
+    SIDECAR_EXTENSIONS
+        .iter()
+        .map(|ext| {
+            let mut name = stem.to_os_string();
+            name.push(format!(".{}", ext));
+            path.with_file_name(name)
+        })
+        .filter(|sidecar| sidecar.is_file())
+        .collect()
+}
+
+/// Where the path ends up after the relocations.
+fn moved_path(relocations: &[Relocation], path: &Path) -> PathBuf {
+    relocations
+        .iter()
+        .find_map(|relocation| relocate::rebase(path, &relocation.from, &relocation.to))
+        .unwrap_or_else(|| path.to_path_buf())
+}
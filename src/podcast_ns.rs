@@ -0,0 +1,224 @@
+//! Typed access to the Podcasting 2.0 namespace (<https://podcastindex.org/namespace/1.0>).
+//!
+//! The types mirror the elements of the namespace, not every field is used by TaleCast itself.
+
+use crate::utils;
+use serde_json::Map;
+use serde_json::Value;
+
+/// A `<podcast:transcript>` element.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub url: String,
+    pub mime: String,
+    pub language: Option<String>,
+    pub rel: Option<String>,
+}
+
+impl Transcript {
+    fn new(val: &Value) -> Option<Self> {
+        Some(Self {
+            url: attr(val, "url")?,
+            mime: attr(val, "type")?,
+            language: attr(val, "language"),
+            rel: attr(val, "rel"),
+        })
+    }
+
+    /// The file extension of the transcript format, which is also how formats are configured.
+    pub fn format(&self) -> Option<&'static str> {
+        let format = match self.mime.as_str() {
+            "application/srt" | "application/x-subrip" | "text/srt" => "srt",
+            "text/vtt" => "vtt",
+            "application/json" => "json",
+            "text/html" => "html",
+            "text/plain" => "txt",
+            _ => return None,
+        };
+
+        Some(format)
+    }
+}
+
+/// A `<podcast:chapters>` element, pointing to a JSON chapters file.
+#[derive(Debug, Clone)]
+pub struct ChaptersLink {
+    pub url: String,
+    pub mime: String,
+}
+
+impl ChaptersLink {
+    fn new(val: &Value) -> Option<Self> {
+        Some(Self {
+            url: attr(val, "url")?,
+            mime: attr(val, "type")?,
+        })
+    }
+}
+
+/// A `<podcast:season>` element.
+#[derive(Debug, Clone)]
+pub struct Season {
+    pub number: u32,
+    pub name: Option<String>,
+}
+
+impl Season {
+    fn new(val: &Value) -> Option<Self> {
+        Some(Self {
+            number: utils::val_to_string(val)?.parse().ok()?,
+            name: attr(val, "name"),
+        })
+    }
+}
+
+/// A `<podcast:episode>` element.
+///
+/// The number may be a decimal, so it's kept as a string.
+#[derive(Debug, Clone)]
+pub struct EpisodeNumber {
+    pub number: String,
+    pub display: Option<String>,
+}
+
+impl EpisodeNumber {
+    fn new(val: &Value) -> Option<Self> {
+        Some(Self {
+            number: utils::val_to_string(val)?,
+            display: attr(val, "display"),
+        })
+    }
+}
+
+/// A `<podcast:person>` element.
+#[derive(Debug, Clone)]
+pub struct Person {
+    pub name: String,
+    pub role: String,
+    pub group: String,
+    pub img: Option<String>,
+    pub href: Option<String>,
+}
+
+impl Person {
+    fn new(val: &Value) -> Option<Self> {
+        Some(Self {
+            name: utils::val_to_string(val)?,
+            role: attr(val, "role").unwrap_or_else(|| "host".to_string()),
+            group: attr(val, "group").unwrap_or_else(|| "cast".to_string()),
+            img: attr(val, "img"),
+            href: attr(val, "href"),
+        })
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.role.eq_ignore_ascii_case("host")
+    }
+}
+
+/// A `<podcast:value>` element with its recipients.
+#[derive(Debug, Clone)]
+pub struct ValueBlock {
+    pub ty: String,
+    pub method: String,
+    pub suggested: Option<String>,
+    pub recipients: Vec<ValueRecipient>,
+}
+
+impl ValueBlock {
+    fn new(val: &Value) -> Option<Self> {
+        let recipients = val
+            .get("podcast:valueRecipient")
+            .map(utils::val_to_vec)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(ValueRecipient::new)
+            .collect();
+
+        Some(Self {
+            ty: attr(val, "type")?,
+            method: attr(val, "method")?,
+            suggested: attr(val, "suggested"),
+            recipients,
+        })
+    }
+}
+
+/// A `<podcast:valueRecipient>` element.
+#[derive(Debug, Clone)]
+pub struct ValueRecipient {
+    pub name: Option<String>,
+    pub ty: String,
+    pub address: String,
+    pub split: u32,
+}
+
+impl ValueRecipient {
+    fn new(val: &Value) -> Option<Self> {
+        Some(Self {
+            name: attr(val, "name"),
+            ty: attr(val, "type")?,
+            address: attr(val, "address")?,
+            split: attr(val, "split")?.parse().ok()?,
+        })
+    }
+}
+
+/// A `<podcast:location>` element.
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub name: String,
+    pub geo: Option<String>,
+    pub osm: Option<String>,
+}
+
+impl Location {
+    fn new(val: &Value) -> Option<Self> {
+        Some(Self {
+            name: utils::val_to_string(val)?,
+            geo: attr(val, "geo"),
+            osm: attr(val, "osm"),
+        })
+    }
+}
+
+pub fn transcripts(raw: &Map<String, Value>) -> Vec<Transcript> {
+    all(raw, "podcast:transcript", Transcript::new)
+}
+
+pub fn chapters(raw: &Map<String, Value>) -> Option<ChaptersLink> {
+    ChaptersLink::new(raw.get("podcast:chapters")?)
+}
+
+pub fn season(raw: &Map<String, Value>) -> Option<Season> {
+    Season::new(raw.get("podcast:season")?)
+}
+
+pub fn episode(raw: &Map<String, Value>) -> Option<EpisodeNumber> {
+    EpisodeNumber::new(raw.get("podcast:episode")?)
+}
+
+pub fn persons(raw: &Map<String, Value>) -> Vec<Person> {
+    all(raw, "podcast:person", Person::new)
+}
+
+pub fn value(raw: &Map<String, Value>) -> Option<ValueBlock> {
+    ValueBlock::new(raw.get("podcast:value")?)
+}
+
+pub fn location(raw: &Map<String, Value>) -> Option<Location> {
+    Location::new(raw.get("podcast:location")?)
+}
+
+fn all<T>(raw: &Map<String, Value>, key: &str, f: fn(&Value) -> Option<T>) -> Vec<T> {
+    raw.get(key)
+        .map(utils::val_to_vec)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(f)
+        .collect()
+}
+
+fn attr(val: &Value, name: &str) -> Option<String> {
+    utils::val_to_string(val.get(format!("@{}", name))?)
+}
@@ -0,0 +1,117 @@
+//! Noticing feeds that have moved for good.
+//!
+//! The http client follows redirects on its own, so its redirect policy records where chains
+//! of permanent redirects (301 and 308) lead. A chain with a temporary redirect in it doesn't
+//! count, the old url is still the one to use. Only the feed requests being watched are
+//! recorded, and only for as long as they're watched.
+
+use reqwest::redirect::{Attempt, Policy};
+use reqwest::StatusCode;
+use reqwest::Url;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// The watched urls, along with where their permanent redirects lead so far.
+static WATCHED: Mutex<Option<HashMap<String, Watched>>> = Mutex::new(None);
+
+/// The same url may be fetched by several podcasts at once, so it's watched until all
+/// of their requests are done.
+#[derive(Default)]
+struct Watched {
+    requests: usize,
+    target: Option<String>,
+    temporary: bool,
+}
+
+/// Same as the default policy of following up to 10 redirects, but recording permanent ones.
+pub fn policy() -> Policy {
+    Policy::custom(|attempt| {
+        if attempt.previous().len() > 10 {
+            return attempt.error("too many redirects");
+        }
+
+        record(&attempt);
+        attempt.follow()
+    })
+}
+
+fn record(attempt: &Attempt) {
+    let Some(original) = attempt.previous().first() else {
+        return;
+    };
+
+    let mut watched = WATCHED.lock().unwrap();
+    let Some(watched) = watched.as_mut() else {
+        return;
+    };
+
+    let permanent = matches!(
+        attempt.status(),
+        StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+    );
+
+    let Some(watched) = watched.get_mut(original.as_str()) else {
+        return;
+    };
+
+    // The chain stops being recorded at its first temporary redirect.
+    if !permanent {
+        watched.temporary = true;
+        watched.target = None;
+    } else if !watched.temporary {
+        watched.target = Some(attempt.url().to_string());
+    }
+}
+
+/// Starts recording the permanent redirects of requests to the url.
+pub fn watch(url: &str) -> Watch {
+    let url = Url::parse(url).ok().map(String::from);
+
+    if let Some(url) = &url {
+        WATCHED
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .entry(url.clone())
+            .or_default()
+            .requests += 1;
+    }
+
+    Watch { url }
+}
+
+/// Stops recording when dropped, whether the request went through or not, once no other
+/// request to the url is being watched.
+pub struct Watch {
+    url: Option<String>,
+}
+
+impl Watch {
+    /// The url that the watched url has permanently moved to, if the response came
+    /// through permanent redirects only.
+    pub fn moved_to(&self, response: &reqwest::Response) -> Option<String> {
+        let url = self.url.as_ref()?;
+        let target = WATCHED.lock().unwrap().as_ref()?.get(url)?.target.clone()?;
+        (target == response.url().as_str()).then_some(target)
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        let Some(url) = &self.url else {
+            return;
+        };
+
+        let mut watched = WATCHED.lock().unwrap();
+        let Some(watched) = watched.as_mut() else {
+            return;
+        };
+
+        if let Some(entry) = watched.get_mut(url) {
+            entry.requests -= 1;
+            if entry.requests == 0 {
+                watched.remove(url);
+            }
+        }
+    }
+}
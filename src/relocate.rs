@@ -0,0 +1,181 @@
+//! Moving or deleting the files of a podcast when it's renamed or removed.
+//!
+//! Only paths that follow from the name of the podcast, such as the default
+//! `{home}/talecast/{podname}` download path, belong to the podcast alone. Other paths may
+//! be shared with other podcasts, so only the symlinks and tracker entries of the podcast
+//! are changed there.
+
+use crate::utils;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// What a path of the podcast is used for, in the order the paths are moved.
+///
+/// Download directories go first, since the other paths are often inside them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PathKind {
+    Download,
+    Partial,
+    Tracker,
+    Symlink,
+}
+
+/// A path of the podcast that changes along with its name.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Relocation {
+    pub kind: PathKind,
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl Relocation {
+    /// Moves the path to its new location, returning whether there was anything to move.
+    ///
+    /// A path inside a directory that was moved before is gone already.
+    pub fn apply(&self) -> Result<bool, String> {
+        if fs::symlink_metadata(&self.from).is_err() {
+            return Ok(false);
+        }
+
+        if fs::symlink_metadata(&self.to).is_ok() {
+            return Err(format!(
+                "can't move {:?}, {:?} already exists",
+                &self.from, &self.to
+            ));
+        }
+
+        if let Some(parent) = self.to.parent() {
+            utils::create_dir(parent);
+        }
+
+        fs::rename(&self.from, &self.to)
+            .map_err(|e| format!("failed to move {:?} to {:?}: {}", &self.from, &self.to, e))?;
+
+        Ok(true)
+    }
+
+    /// Moves the path back to where it was before [`Relocation::apply`].
+    pub fn undo(&self) -> Result<(), String> {
+        fs::rename(&self.to, &self.from).map_err(|e| {
+            format!(
+                "failed to move {:?} back to {:?}: {}",
+                &self.to, &self.from, e
+            )
+        })
+    }
+
+    /// Deletes the path, returning whether there was anything to delete.
+    pub fn delete(&self) -> Result<bool, String> {
+        let Ok(metadata) = fs::symlink_metadata(&self.from) else {
+            return Ok(false);
+        };
+
+        let result = match metadata.is_dir() {
+            true => fs::remove_dir_all(&self.from),
+            false => fs::remove_file(&self.from),
+        };
+
+        result.map_err(|e| format!("failed to delete {:?}: {}", &self.from, e))?;
+        Ok(true)
+    }
+}
+
+/// Moves all of the paths or none of them, returning the ones that were moved.
+///
+/// The targets are checked before anything is moved, and if moving a path fails anyway,
+/// for example across file systems, the paths moved before it are moved back.
+pub fn apply_all(relocations: &[Relocation]) -> Result<Vec<&Relocation>, String> {
+    for relocation in relocations {
+        let exists = |path: &Path| fs::symlink_metadata(path).is_ok();
+        if exists(&relocation.from) && exists(&relocation.to) {
+            return Err(format!(
+                "can't move {:?}, {:?} already exists",
+                &relocation.from, &relocation.to
+            ));
+        }
+    }
+
+    let mut applied = vec![];
+    for relocation in relocations {
+        match relocation.apply() {
+            Ok(true) => applied.push(relocation),
+            Ok(false) => {}
+            Err(e) => {
+                undo_all(&applied)?;
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(applied)
+}
+
+/// Moves the paths back in reverse order.
+pub fn undo_all(applied: &[&Relocation]) -> Result<(), String> {
+    for relocation in applied.iter().rev() {
+        relocation.undo()?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the `from` prefix of the path with `to`, if it's inside `from`.
+pub fn rebase(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    let rest = path.strip_prefix(from).ok()?;
+
+    match rest.as_os_str().is_empty() {
+        true => Some(to.to_path_buf()),
+        false => Some(to.join(rest)),
+    }
+}
+
+/// Points the symlinks in the directory that lead into `from` into `to` instead,
+/// returning how many were changed.
+pub fn relink(dir: &Path, from: &Path, to: &Path) -> Result<usize, String> {
+    let mut relinked = 0;
+
+    for (link, target) in symlinks(dir) {
+        let Some(target) = rebase(&target, from, to) else {
+            continue;
+        };
+
+        fs::remove_file(&link)
+            .and_then(|_| std::os::unix::fs::symlink(&target, &link))
+            .map_err(|e| format!("failed to update symlink {:?}: {}", &link, e))?;
+        relinked += 1;
+    }
+
+    Ok(relinked)
+}
+
+/// Deletes the symlinks in the directory that lead into `target_dir`,
+/// returning how many were deleted.
+pub fn unlink(dir: &Path, target_dir: &Path) -> Result<usize, String> {
+    let mut unlinked = 0;
+
+    for (link, target) in symlinks(dir) {
+        if target.starts_with(target_dir) {
+            fs::remove_file(&link)
+                .map_err(|e| format!("failed to delete symlink {:?}: {}", &link, e))?;
+            unlinked += 1;
+        }
+    }
+
+    Ok(unlinked)
+}
+
+/// The symlinks in the directory, along with their targets.
+fn symlinks(dir: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let target = fs::read_link(entry.path()).ok()?;
+            Some((entry.path(), target))
+        })
+        .collect()
+}
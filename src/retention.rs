@@ -0,0 +1,85 @@
+//! Deleting downloaded episodes that fall outside the retention settings of a podcast.
+//!
+//! Deleted episodes stay in the tracker, so they aren't downloaded again on the next sync.
+
+use crate::utils;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Retention {
+    /// How many of the most recently downloaded episodes are kept.
+    pub keep_episodes: Option<usize>,
+    /// How many days episodes are kept after they're downloaded.
+    pub keep_days: Option<u64>,
+    /// How many bytes the downloaded episodes of the podcast may take up in total.
+    pub max_size: Option<u64>,
+}
+
+impl Retention {
+    pub fn is_enabled(&self) -> bool {
+        self.keep_episodes.is_some() || self.keep_days.is_some() || self.max_size.is_some()
+    }
+
+    /// The stored episodes to delete, the most recently downloaded ones are kept first.
+    pub fn expired(&self, mut stored: Vec<StoredEpisode>) -> Vec<StoredEpisode> {
+        stored.sort_by_key(|episode| std::cmp::Reverse(episode.downloaded));
+
+        let now = utils::current_unix().as_secs();
+        let mut kept = 0;
+        let mut size = 0;
+
+        stored
+            .into_iter()
+            .filter(|episode| {
+                let too_many = self.keep_episodes.is_some_and(|max| kept >= max);
+                let too_old = self
+                    .keep_days
+                    .is_some_and(|days| now.saturating_sub(episode.downloaded) > days * 86400);
+                let too_large = self.max_size.is_some_and(|max| size + episode.size > max);
+
+                let expired = too_many || too_old || too_large;
+                if !expired {
+                    kept += 1;
+                    size += episode.size;
+                }
+                expired
+            })
+            .collect()
+    }
+}
+
+/// A downloaded episode that's still on disk.
+#[derive(Debug, Clone)]
+pub struct StoredEpisode {
+    pub title: String,
+    /// The guid of the episode, if it's still in the feed.
+    pub guid: Option<String>,
+    /// When the episode was downloaded, in unix seconds.
+    pub downloaded: u64,
+    pub path: PathBuf,
+    pub size: u64,
+    pub sidecars: Vec<PathBuf>,
+    pub symlink: Option<PathBuf>,
+}
+
+impl StoredEpisode {
+    /// Deletes the episode along with its sidecar files and symlink.
+    pub fn delete(&self) -> Result<(), String> {
+        fs::remove_file(&self.path)
+            .map_err(|e| format!("failed to delete {:?}: {}", &self.path, e))?;
+
+        for sidecar in &self.sidecars {
+            let _ = fs::remove_file(sidecar);
+        }
+
+        // Only a symlink to the deleted episode, not a file that took its name.
+        if let Some(symlink) = &self.symlink {
+            if fs::read_link(symlink).is_ok_and(|target| target == self.path) {
+                let _ = fs::remove_file(symlink);
+            }
+        }
+
+        Ok(())
+    }
+}
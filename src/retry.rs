@@ -0,0 +1,124 @@
+//! Retrying feed fetches and enclosure downloads that failed along the way.
+
+use crate::config::RetrySettings;
+use crate::display::DownloadBar;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::time;
+
+/// A failed attempt, and whether it's worth trying again.
+#[derive(Debug)]
+pub struct Failure {
+    pub error: String,
+    pub retryable: bool,
+    /// How long the server asked us to wait, from the `Retry-After` header.
+    pub retry_after: Option<time::Duration>,
+}
+
+impl Failure {
+    /// Connection failures and interrupted transfers are worth retrying.
+    pub fn transient(error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            retryable: true,
+            retry_after: None,
+        }
+    }
+
+    pub fn permanent(error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            retryable: false,
+            retry_after: None,
+        }
+    }
+
+    /// A response with an unsuccessful status, retried if the settings say so.
+    pub fn from_status(response: &reqwest::Response, settings: &RetrySettings) -> Self {
+        let status = response.status();
+
+        Self {
+            error: format!("unexpected status: {}", status),
+            retryable: settings.retries_status(status),
+            retry_after: match status.as_u16() {
+                429 | 503 => retry_after(response),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Parses the `Retry-After` header, which is either a number of seconds or a date.
+fn retry_after(response: &reqwest::Response) -> Option<time::Duration> {
+    let value = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(time::Duration::from_secs(secs));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let secs = (date.timestamp() - chrono::Utc::now().timestamp()).max(0);
+    Some(time::Duration::from_secs(secs as u64))
+}
+
+/// Scales the delay by a random factor between 0.5 and 1.
+fn jittered(delay: time::Duration) -> time::Duration {
+    let random = RandomState::new().hash_one(time::SystemTime::now());
+    let factor = 0.5 + (random as f64 / u64::MAX as f64) / 2.;
+    delay.mul_f64(factor)
+}
+
+/// Runs the attempt until it succeeds, fails permanently, or runs out of attempts.
+pub async fn with_retries<T, F, Fut>(
+    settings: &RetrySettings,
+    ui: &DownloadBar,
+    mut attempt: F,
+) -> Result<T, String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Failure>>,
+{
+    let attempts = settings.attempts();
+    let mut retry = 1;
+
+    loop {
+        let failure = match attempt().await {
+            Ok(val) => return Ok(val),
+            Err(failure) => failure,
+        };
+
+        if !failure.retryable || retry >= attempts {
+            return Err(failure.error);
+        }
+
+        let delay = match failure.retry_after {
+            // Not worth holding up the sync for, try again on the next one.
+            Some(delay) if delay > settings.max_backoff() => {
+                return Err(format!(
+                    "{}, server asked to retry after {:?}",
+                    failure.error, delay
+                ));
+            }
+            Some(delay) => delay,
+            None if settings.jitter() => jittered(settings.backoff(retry)),
+            None => settings.backoff(retry),
+        };
+
+        ui.log_warn(format!(
+            "{}, retrying in {:?} ({}/{})",
+            failure.error,
+            delay,
+            retry + 1,
+            attempts
+        ));
+
+        tokio::time::sleep(delay).await;
+        retry += 1;
+    }
+}
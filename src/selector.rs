@@ -0,0 +1,43 @@
+use crate::episode::Episode;
+use crate::utils::Unix;
+use regex::Regex;
+
+/// Selects episodes of a podcast from the command line.
+///
+/// Guids and indices pick out specific episodes, while the title and date range
+/// narrow down the selection further.
+#[derive(Debug, Default, Clone)]
+pub struct EpisodeSelector {
+    pub guids: Vec<String>,
+    pub indices: Vec<usize>,
+    pub title: Option<Regex>,
+    pub after: Option<Unix>,
+    pub before: Option<Unix>,
+}
+
+impl EpisodeSelector {
+    pub fn is_empty(&self) -> bool {
+        self.guids.is_empty()
+            && self.indices.is_empty()
+            && self.title.is_none()
+            && self.after.is_none()
+            && self.before.is_none()
+    }
+
+    pub fn matches(&self, episode: &Episode) -> bool {
+        let picked = (self.guids.is_empty() && self.indices.is_empty())
+            || self.guids.iter().any(|guid| guid == episode.attrs.guid())
+            || self.indices.contains(&episode.index);
+
+        let title_matches = self
+            .title
+            .as_ref()
+            .is_none_or(|title| title.is_match(episode.attrs.title()));
+
+        let published = episode.attrs.published();
+        let after = self.after.is_none_or(|after| published > after);
+        let before = self.before.is_none_or(|before| published < before);
+
+        picked && title_matches && after && before
+    }
+}
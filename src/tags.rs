@@ -3,95 +3,230 @@ use crate::episode;
 use crate::podcast::RawPodcast;
 use chrono::Datelike;
 use id3::TagLike;
+use lofty::tag::ItemKey;
+
+/// Metadata of an episode extracted from the feed, independent of the audio container.
+///
+/// It gets written as ID3v2 frames for MP3 files, and through [`lofty`] as MP4 atoms or
+/// Vorbis comments for the other supported containers.
+#[derive(Debug, Clone)]
+pub struct Metadata {
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: String,
+    pub genre: String,
+    pub track: Option<u32>,
+    pub disc: Option<u32>,
+    pub released: chrono::DateTime<chrono::Utc>,
+    pub copyright: Option<String>,
+    pub description: Option<String>,
+    pub categories: Vec<String>,
+    pub language: Option<String>,
+    /// Duration in milliseconds.
+    pub duration: Option<u32>,
+    pub publisher: Option<String>,
+    pub podcast_id: String,
+}
 
-pub async fn extract_tags_from_raw(
-    podcast: &RawPodcast,
-    episode: &episode::Attributes,
-    ui: &DownloadBar,
-) -> Option<id3::Tag> {
-    let mut tags = id3::Tag::new();
+impl Metadata {
+    pub fn to_id3(&self) -> id3::Tag {
+        use chrono::Timelike;
 
-    tags.set_title(episode.title());
+        let mut tags = id3::Tag::new();
 
-    if let Ok(author) = episode.author() {
-        ui.log_trace("extracting author tag");
-        tags.set_artist(author);
-    }
+        tags.set_title(&self.title);
+
+        if let Some(artist) = &self.artist {
+            tags.set_artist(artist);
+        }
+
+        tags.set_album(&self.album);
+        tags.set_genre(&self.genre);
+
+        if let Some(track) = self.track {
+            tags.set_track(track);
+        }
+
+        if let Some(disc) = self.disc {
+            tags.set_disc(disc);
+        }
+
+        tags.set_year(self.released.year());
+
+        if let Some(copyright) = &self.copyright {
+            tags.set_text(Id3Tag::COPYRIGHT, copyright);
+        }
+
+        if let Some(desc) = &self.description {
+            tags.set_text(Id3Tag::DESCRIPTION, desc);
+        }
 
-    tags.set_album(podcast.title());
+        if !self.categories.is_empty() {
+            tags.set_text_values(Id3Tag::PODCASTCATEGORY, &self.categories);
+        }
+
+        let datetime = self.released;
+        let ts = id3::frame::Timestamp {
+            year: datetime.year(),
+            month: Some(datetime.month() as u8),
+            day: Some(datetime.day() as u8),
+            hour: Some(datetime.hour() as u8),
+            minute: Some(datetime.minute() as u8),
+            second: Some(datetime.second() as u8),
+        };
+
+        tags.set_date_released(ts);
 
-    tags.set_genre("podcast");
+        if let Some(language) = &self.language {
+            tags.set_text(Id3Tag::LANGUAGE, language);
+        }
+
+        if let Some(duration) = self.duration {
+            tags.set_text(Id3Tag::DURATION, duration.to_string());
+        }
 
-    if let Ok(episode) = episode.itunes_episode() {
-        if let Ok(episode) = episode.parse::<u32>() {
-            ui.log_trace("extracting itunes track number");
-            tags.set_track(episode);
+        if let Some(publisher) = &self.publisher {
+            tags.set_text(Id3Tag::PUBLISHER, publisher);
         }
+
+        tags.set_text(Id3Tag::PODCAST_ID, &self.podcast_id);
+
+        tags
+    }
+
+    /// The metadata as generic items, which [`lofty`] maps to the keys of the container.
+    ///
+    /// Each item lists its keys in order of preference, as not every container supports every key.
+    pub fn to_items(&self) -> Vec<(Vec<ItemKey>, String)> {
+        let items = [
+            (vec![ItemKey::TrackTitle], Some(self.title.clone())),
+            (vec![ItemKey::TrackArtist], self.artist.clone()),
+            (vec![ItemKey::AlbumTitle], Some(self.album.clone())),
+            (vec![ItemKey::Genre], Some(self.genre.clone())),
+            (
+                vec![ItemKey::TrackNumber],
+                self.track.map(|x| x.to_string()),
+            ),
+            (vec![ItemKey::DiscNumber], self.disc.map(|x| x.to_string())),
+            (vec![ItemKey::Year], Some(self.released.year().to_string())),
+            (
+                vec![ItemKey::ReleaseDate, ItemKey::RecordingDate],
+                Some(self.released.format("%Y-%m-%d").to_string()),
+            ),
+            (vec![ItemKey::CopyrightMessage], self.copyright.clone()),
+            (
+                vec![ItemKey::PodcastDescription, ItemKey::Comment],
+                self.description.clone(),
+            ),
+            (
+                vec![ItemKey::PodcastSeriesCategory],
+                Some(self.categories.join(", ")).filter(|x| !x.is_empty()),
+            ),
+            (vec![ItemKey::Language], self.language.clone()),
+            (vec![ItemKey::Length], self.duration.map(|x| x.to_string())),
+            (vec![ItemKey::Publisher], self.publisher.clone()),
+            (
+                vec![ItemKey::PodcastGlobalUniqueId],
+                Some(self.podcast_id.clone()),
+            ),
+        ];
+
+        items
+            .into_iter()
+            .filter_map(|(keys, val)| Some((keys, val?)))
+            .collect()
     }
+}
+
+pub async fn extract_tags_from_raw(
+    podcast: &RawPodcast,
+    episode: &episode::Attributes,
+    ui: &DownloadBar,
+) -> Metadata {
+    let artist = match episode.author() {
+        Ok(author) => {
+            ui.log_trace("extracting author tag");
+            Some(author.to_string())
+        }
+        Err(_) => {
+            let hosts: Vec<String> = episode
+                .persons()
+                .into_iter()
+                .filter(|person| person.is_host())
+                .map(|person| person.name)
+                .collect();
+
+            if hosts.is_empty() {
+                None
+            } else {
+                ui.log_trace("extracting artist tag from podcast:person hosts");
+                Some(hosts.join(", "))
+            }
+        }
+    };
+
+    let track = episode.episode_number().and_then(|number| {
+        let number = number.number.parse::<u32>().ok()?;
+        ui.log_trace("extracting episode track number");
+        Some(number)
+    });
 
-    let year = chrono::DateTime::from_timestamp(episode.published().as_secs() as i64, 0)
-        .unwrap()
-        .year();
-    tags.set_year(year);
+    let disc = episode.season().map(|season| {
+        ui.log_trace("extracting season disc number");
+        season.number
+    });
 
-    if let Some(copyright) = podcast.copyright() {
+    let copyright = podcast.copyright().map(|copyright| {
         ui.log_trace("extracting copyright tag");
-        tags.set_text(Id3Tag::COPYRIGHT, copyright);
-    }
+        copyright.to_string()
+    });
 
-    if let Ok(desc) = episode.description() {
+    let description = episode.description().ok().map(|desc| {
         ui.log_trace("extracting description tag");
-        tags.set_text(Id3Tag::DESCRIPTION, desc);
-    }
+        desc.to_string()
+    });
 
-    let mut strs = vec![];
-    for cat in podcast.categories() {
-        strs.push(cat);
-    }
+    let categories: Vec<String> = podcast.categories().into_iter().map(String::from).collect();
 
-    if !strs.is_empty() {
+    if !categories.is_empty() {
         ui.log_trace("extracting podcast categories tag");
-        tags.set_text_values(Id3Tag::PODCASTCATEGORY, strs);
     }
 
-    use chrono::TimeZone;
-    use chrono::Timelike;
-    let datetime = chrono::Utc
-        .timestamp_opt(episode.published().as_secs() as i64, 0)
-        .unwrap();
-
-    let ts = id3::frame::Timestamp {
-        year: datetime.year(),
-        month: Some(datetime.month() as u8),
-        day: Some(datetime.day() as u8),
-        hour: Some(datetime.hour() as u8),
-        minute: Some(datetime.minute() as u8),
-        second: Some(datetime.second() as u8),
-    };
-
-    tags.set_date_released(ts);
+    let released =
+        chrono::DateTime::from_timestamp(episode.published().as_secs() as i64, 0).unwrap();
 
-    if let Some(language) = podcast.language() {
+    let language = podcast.language().map(|language| {
         ui.log_trace("extracting language tag");
-        tags.set_text(Id3Tag::LANGUAGE, language);
-    }
+        language.to_string()
+    });
 
-    if let Ok(dur) = episode.itunes_duration() {
-        if let Ok(secs) = dur.parse::<u32>() {
-            ui.log_trace("extracting itunes duration tag");
-            let millis = secs * 1000;
-            tags.set_text(Id3Tag::DURATION, millis.to_string());
-        }
-    }
+    let duration = episode.itunes_duration().ok().and_then(|dur| {
+        let secs = dur.parse::<u32>().ok()?;
+        ui.log_trace("extracting itunes duration tag");
+        Some(secs * 1000)
+    });
 
-    if let Some(author) = podcast.author() {
+    let publisher = podcast.author().map(|author| {
         ui.log_trace("extracting publisher tag");
-        tags.set_text(Id3Tag::PUBLISHER, author);
+        author.to_string()
+    });
+
+    Metadata {
+        title: episode.title().to_string(),
+        artist,
+        album: podcast.title().to_string(),
+        genre: "podcast".to_string(),
+        track,
+        disc,
+        released,
+        copyright,
+        description,
+        categories,
+        language,
+        duration,
+        publisher,
+        podcast_id: episode.guid().to_string(),
     }
-
-    tags.set_text(Id3Tag::PODCAST_ID, episode.guid());
-
-    Some(tags)
 }
 
 struct Id3Tag;
@@ -0,0 +1,165 @@
+//! Limits on how many episodes are downloaded at the same time, and how fast.
+//!
+//! The limits per podcast are enforced by the podcast itself, this covers the limits
+//! shared by all podcasts: the total number of transfers, the number of transfers per host
+//! and the total bandwidth.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
+
+#[derive(Debug)]
+pub struct TransferLimits {
+    global: Option<Arc<Semaphore>>,
+    per_host: Option<usize>,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+    rate: RateLimiter,
+}
+
+/// Allows a transfer to proceed until it's dropped.
+pub struct TransferPermit {
+    _host: Option<OwnedSemaphorePermit>,
+    _global: Option<OwnedSemaphorePermit>,
+}
+
+impl TransferLimits {
+    /// A limit of `None` means unlimited.
+    pub fn new(global: Option<usize>, per_host: Option<usize>, rate: RateLimiter) -> Self {
+        Self {
+            global: global.map(|max| Arc::new(Semaphore::new(max.max(1)))),
+            per_host: per_host.map(|max| max.max(1)),
+            hosts: Mutex::default(),
+            rate,
+        }
+    }
+
+    /// The bandwidth limit shared by all transfers.
+    pub fn rate(&self) -> &RateLimiter {
+        &self.rate
+    }
+
+    fn host_semaphore(&self, url: &str) -> Option<Arc<Semaphore>> {
+        let max = self.per_host?;
+        let host = reqwest::Url::parse(url).ok()?.host_str()?.to_string();
+
+        let mut hosts = self.hosts.lock().unwrap();
+        let semaphore = hosts
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(max)));
+
+        Some(Arc::clone(semaphore))
+    }
+
+    /// Waits until a transfer from the given url is allowed.
+    ///
+    /// The host permit is acquired first, so that a transfer waiting on a busy host
+    /// doesn't hold up transfers from other hosts.
+    pub async fn acquire(&self, url: &str) -> TransferPermit {
+        let host = match self.host_semaphore(url) {
+            Some(semaphore) => semaphore.acquire_owned().await.ok(),
+            None => None,
+        };
+
+        let global = match &self.global {
+            Some(semaphore) => Arc::clone(semaphore).acquire_owned().await.ok(),
+            None => None,
+        };
+
+        TransferPermit {
+            _host: host,
+            _global: global,
+        }
+    }
+}
+
+/// A time of day during which a different rate limit applies.
+///
+/// The window wraps around midnight if it ends before it starts.
+#[derive(Debug, Clone)]
+pub struct RateWindow {
+    pub start: chrono::NaiveTime,
+    pub end: chrono::NaiveTime,
+    pub rate: Option<u64>,
+}
+
+impl RateWindow {
+    fn contains(&self, time: chrono::NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Bytes that may be transferred right away, negative when transfers are ahead of the rate.
+    available: f64,
+    refilled: time::Instant,
+}
+
+/// A token bucket limiting the bytes per second of the transfers sharing it.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: Option<u64>,
+    schedule: Vec<RateWindow>,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    /// A rate of `None` means unlimited, outside of the windows of the schedule.
+    pub fn new(rate: Option<u64>, schedule: Vec<RateWindow>) -> Self {
+        Self {
+            rate,
+            schedule,
+            bucket: Mutex::new(Bucket {
+                available: 0.,
+                refilled: time::Instant::now(),
+            }),
+        }
+    }
+
+    fn current_rate(&self) -> Option<u64> {
+        let now = chrono::Local::now().time();
+
+        let rate = match self.schedule.iter().find(|window| window.contains(now)) {
+            Some(window) => window.rate,
+            None => self.rate,
+        };
+
+        rate.filter(|rate| *rate > 0)
+    }
+
+    /// Accounts for the transferred bytes, waiting as long as it takes to get back under the rate.
+    pub async fn throttle(&self, bytes: usize) {
+        let Some(rate) = self.current_rate() else {
+            return;
+        };
+
+        let rate = rate as f64;
+
+        let delay = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = time::Instant::now();
+            let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+
+            // At most a second worth of bytes can be saved up, to keep bursts short.
+            bucket.available = (bucket.available + elapsed * rate).min(rate);
+            bucket.available -= bytes as f64;
+            bucket.refilled = now;
+
+            match bucket.available {
+                available if available < 0. => time::Duration::from_secs_f64(-available / rate),
+                _ => time::Duration::ZERO,
+            }
+        };
+
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
@@ -1,5 +1,10 @@
+use crate::cache;
 use crate::config;
 use crate::episode::Episode;
+use crate::podcast;
+use crate::redirects;
+use crate::retry;
+use crate::retry::Failure;
 use crate::utils;
 use regex::Regex;
 use serde_json::Value;
@@ -53,6 +58,17 @@ pub fn cache_dir() -> PathBuf {
     path
 }
 
+/// Unlike the cache directory, it's only created once something is written to it,
+/// so that a dry run leaves no trace.
+pub fn data_dir() -> PathBuf {
+    match std::env::var("XDG_DATA_HOME") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => dirs::data_dir()
+            .expect("unable to locate data directory. Try setting 'XDG_DATA_HOME' manually"),
+    }
+    .join(crate::APPNAME)
+}
+
 pub fn current_unix() -> Unix {
     let secs = chrono::Utc::now().timestamp() as u64;
     Unix::from_secs(secs)
@@ -141,20 +157,91 @@ pub fn _handle_response(response: Result<reqwest::Response, reqwest::Error>) ->
 use crate::display::DownloadBar;
 use futures_util::StreamExt;
 
+/// The text of a downloaded feed along with its content type.
+pub struct FeedText {
+    pub text: String,
+    pub content_type: Option<String>,
+    /// Where the feed has permanently moved to, see [`redirects`].
+    pub moved_to: Option<String>,
+}
+
+impl FeedText {
+    /// JSON feeds are detected by their content type, or failing that, a leading `{`.
+    pub fn is_json(&self) -> bool {
+        let json_type = self
+            .content_type
+            .as_deref()
+            .is_some_and(|ty| ty.contains("json"));
+
+        json_type || self.text.trim_start().starts_with('{')
+    }
+}
+
+/// Downloads a feed, using and updating the feed cache if `use_cache` is set.
 pub async fn download_text(
     client: &reqwest::Client,
     url: &str,
+    use_cache: bool,
+    retry: &config::RetrySettings,
     ui: &DownloadBar,
-) -> Option<String> {
-    ui.log_info("downloading podcast xml");
-    let response = match client.get(url).send().await {
-        Ok(res) => res,
+) -> Option<FeedText> {
+    ui.log_info("downloading podcast feed");
+
+    let result =
+        retry::with_retries(retry, ui, || fetch_text(client, url, use_cache, retry, ui)).await;
+
+    match result {
+        Ok(feed) => Some(feed),
         Err(e) => {
-            ui.log_error(&format!("connection failure: {:?}", e));
-            return None;
+            ui.log_error(&e);
+            None
         }
+    }
+}
+
+async fn fetch_text(
+    client: &reqwest::Client,
+    url: &str,
+    use_cache: bool,
+    retry: &config::RetrySettings,
+    ui: &DownloadBar,
+) -> Result<FeedText, Failure> {
+    let mut request = client.get(url);
+
+    let cached_headers = match use_cache {
+        true => cache::FeedCache::headers(url),
+        false => None,
     };
 
+    if let Some(headers) = &cached_headers {
+        if let Some(etag) = &headers.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &headers.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let watch = redirects::watch(url);
+    let response = request
+        .send()
+        .await
+        .map_err(|e| Failure::transient(format!("connection failure: {:?}", e)))?;
+
+    let moved_to = watch.moved_to(&response);
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        ui.log_info("feed not modified since last sync, using cached copy");
+        return cache::FeedCache::feed(url)
+            .map(|feed| FeedText { moved_to, ..feed })
+            .ok_or_else(|| Failure::permanent("cached feed is missing"));
+    }
+
+    if !response.status().is_success() {
+        return Err(Failure::from_status(&response, retry));
+    }
+
+    let headers = cache::FeedHeaders::from_response(&response);
     let total_size = response.content_length().unwrap_or(0);
 
     let mut downloaded = 0;
@@ -163,19 +250,41 @@ pub async fn download_text(
     ui.init_download_bar(downloaded, total_size);
     let mut buffer: Vec<u8> = vec![];
     while let Some(item) = stream.next().await {
-        let chunk = item.ok()?;
+        let chunk =
+            item.map_err(|e| Failure::transient(format!("feed download interrupted: {}", e)))?;
         buffer.extend(&chunk);
         downloaded = std::cmp::min(downloaded + (chunk.len() as u64), total_size);
         ui.set_progress(downloaded);
     }
 
-    match String::from_utf8(buffer) {
-        Ok(s) => Some(s),
-        Err(e) => {
-            ui.log_error(&format!("failed to decode xml: {:?}", e));
-            None
-        }
+    let text = String::from_utf8(buffer)
+        .map_err(|e| Failure::permanent(format!("failed to decode feed: {:?}", e)))?;
+
+    if use_cache && cache::FeedCache::save(url, &text, &headers).is_none() {
+        ui.log_warn("failed to cache feed");
     }
+
+    Ok(FeedText {
+        text,
+        content_type: headers.content_type,
+        moved_to,
+    })
+}
+
+/// Downloads a small file in one go, such as a transcript.
+pub async fn download_file(client: &reqwest::Client, url: &str, path: &Path) -> Result<(), String> {
+    let response = short_handle_response(client.get(url).send().await)?;
+
+    if !response.status().is_success() {
+        return Err(format!("unexpected status: {}", response.status()));
+    }
+
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|_| "failed to read response".to_string())?;
+
+    fs::write(path, &bytes).map_err(|_| "failed to write file".to_string())
 }
 
 pub fn edit_file(path: &Path) {
@@ -302,6 +411,7 @@ pub async fn search_podcasts(config: &config::GlobalConfig, query: String, catch
         indices.push(num - 1);
     }
 
+    let client = config::init_reqwest_client(config);
     let mut regex_parts = vec![];
     for index in indices {
         let name = results[index]
@@ -315,12 +425,21 @@ pub async fn search_podcasts(config: &config::GlobalConfig, query: String, catch
         let name = trim_quotes(&name);
         let url = trim_quotes(&url);
 
+        let ui = DownloadBar::hidden(name.clone(), config.style());
+        match podcast::fetch_feed(&client, &url, config, &ui).await {
+            Ok(summary) => summary.print(),
+            Err(e) => {
+                eprintln!("'{}' not added: {}", name, e);
+                continue;
+            }
+        }
+
         let podcast = config::PodcastConfig::new(url);
 
         if config::PodcastConfigs::push(name.clone(), podcast) {
             eprintln!("'{}' added!", name);
             if catch_up {
-                regex_parts.push(format!("^{}$", &name));
+                regex_parts.push(format!("^{}$", regex::escape(&name)));
             }
         } else {
             eprintln!("'{}' already exists!", name);
@@ -329,8 +448,10 @@ pub async fn search_podcasts(config: &config::GlobalConfig, query: String, catch
 
     if catch_up && !regex_parts.is_empty() {
         let regex = regex_parts.join("|");
-        let filter = Regex::new(&regex).unwrap();
-        config::PodcastConfigs::catch_up(Some(filter));
+        match Regex::new(&regex) {
+            Ok(filter) => config::PodcastConfigs::catch_up(Some(filter)),
+            Err(e) => eprintln!("error: failed to catch up the added podcasts: {}", e),
+        }
     }
 }
 
@@ -349,31 +470,37 @@ pub fn date_str_to_unix(date: &str) -> Result<time::Duration, String> {
     Ok(time::Duration::from_secs(secs as u64))
 }
 
-pub fn get_extension_from_response(response: &reqwest::Response, episode: &Episode) -> String {
-    let url = &episode.attrs.url();
-    let ext = match PathBuf::from(url)
-        .extension()
-        .and_then(|ext| ext.to_str().map(String::from))
-    {
-        Some(ext) => ext.to_string(),
-        None => {
-            let content_type = response
-                .headers()
-                .get(reqwest::header::CONTENT_TYPE)
-                .and_then(|ct| ct.to_str().ok())
-                .unwrap_or("application/octet-stream");
-
-            let extensions = mime_guess::get_mime_extensions_str(&content_type).unwrap();
-
-            match extensions.contains(&"mp3") {
-                true => "mp3".to_owned(),
-                false => extensions
-                    .first()
-                    .expect("extension not found.")
-                    .to_string(),
-            }
+/// Parses durations such as `"90"`, `"01:30"` and `"00:01:30.500"`.
+pub fn parse_duration(s: &str) -> Option<time::Duration> {
+    let mut secs = 0.;
+    for part in s.trim().split(':') {
+        let part: f64 = part.parse().ok()?;
+        if !part.is_finite() || part < 0. {
+            return None;
         }
-    };
+        secs = secs * 60. + part;
+    }
+
+    // Finite parts can still add up to more than a duration holds.
+    time::Duration::try_from_secs_f64(secs).ok()
+}
+
+/// The extension of a downloaded episode, from its url or the content type it was served with.
+pub fn get_extension(episode: &Episode, content_type: Option<&str>) -> String {
+    extension_from_url(episode.attrs.url())
+        .or_else(|| extension_from_mime(content_type.unwrap_or("application/octet-stream")))
+        .expect("extension not found.")
+}
+
+/// Guesses the extension of an episode before downloading it, from its url or the mime type in the feed.
+pub fn guess_extension(episode: &Episode) -> Option<String> {
+    extension_from_url(episode.attrs.url()).or_else(|| extension_from_mime(episode.attrs.mime()?))
+}
+
+fn extension_from_url(url: &str) -> Option<String> {
+    let ext = PathBuf::from(url)
+        .extension()
+        .and_then(|ext| ext.to_str().map(String::from))?;
 
     // Some urls have these arguments after the extension.
     // feels a bit hacky.
@@ -382,7 +509,16 @@ pub fn get_extension_from_response(response: &reqwest::Response, episode: &Episo
         .split_once("?")
         .map(|(l, _)| l.to_string())
         .unwrap_or(ext);
-    ext
+    Some(ext)
+}
+
+fn extension_from_mime(mime: &str) -> Option<String> {
+    let extensions = mime_guess::get_mime_extensions_str(mime)?;
+
+    match extensions.contains(&"mp3") {
+        true => Some("mp3".to_owned()),
+        false => extensions.first().map(|ext| ext.to_string()),
+    }
 }
 
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
@@ -417,6 +553,37 @@ pub fn val_to_str<'a>(val: &'a serde_json::Value) -> Option<&'a str> {
     obj.get("#text")?.as_str()
 }
 
+/// Like [`val_to_str`], but also accepts numbers and booleans, which the
+/// xml conversion infers from the text of an element.
+pub fn val_to_string(val: &serde_json::Value) -> Option<String> {
+    if let Some(s) = val_to_str(val) {
+        return Some(s.to_string());
+    }
+
+    let val = match val.as_object() {
+        Some(obj) => obj.get("#text")?,
+        None => val,
+    };
+
+    match val {
+        serde_json::Value::Number(num) => Some(num.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// The text of a key in a feed element that was converted to json.
+pub fn map_text(map: &serde_json::Map<String, Value>, key: &str) -> Option<String> {
+    val_to_str(map.get(key)?).map(String::from)
+}
+
+/// Inserts a key unless the feed already has it, so mapped keys never replace the originals.
+pub fn insert_missing(map: &mut serde_json::Map<String, Value>, key: &str, val: impl Into<Value>) {
+    if !map.contains_key(key) {
+        map.insert(key.to_string(), val.into());
+    }
+}
+
 pub fn val_to_url<'a>(val: &'a serde_json::Value) -> Option<&'a str> {
     if let Some(val) = val.as_str() {
         return Some(val);
@@ -439,6 +606,14 @@ pub fn val_to_url<'a>(val: &'a serde_json::Value) -> Option<&'a str> {
     obj.get("uri")?.as_str()
 }
 
+/// Elements that may appear multiple times are only parsed as an array if they actually do.
+pub fn val_to_vec(val: &serde_json::Value) -> Vec<&serde_json::Value> {
+    match val {
+        serde_json::Value::Array(vals) => vals.iter().collect(),
+        val => vec![val],
+    }
+}
+
 pub fn parse_quoted_words(line: &str) -> Option<(String, String)> {
     let (key, val) = line.split_once(" ")?;
     let key = trim_quotes(key);
@@ -463,6 +638,53 @@ pub fn get_file_map_val(file_path: &Path, key: &str) -> Option<String> {
     None
 }
 
+/// Writes the file through a temporary file that's renamed over it, so that other processes
+/// never see a partially written file.
+///
+/// A symlinked file is written where it points to, keeping the symlink and its permissions.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let path = &fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let permissions = fs::metadata(path)
+        .ok()
+        .map(|metadata| metadata.permissions());
+
+    let Some(file_name) = path.file_name() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "path has no file name",
+        ));
+    };
+
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(
+        ".{}-{}.tmp",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = File::create(&tmp_path)
+        .and_then(|mut file| {
+            file.write_all(contents.as_ref())?;
+            if let Some(permissions) = permissions {
+                file.set_permissions(permissions)?;
+            }
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp_path, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
 pub fn append_to_config(file_path: &Path, key: &str, value: &str) -> io::Result<()> {
     let mut file = fs::OpenOptions::new()
         .write(true)